@@ -58,6 +58,9 @@ layout(binding = 0) uniform Common {
     float t_delta;
     // Total time
     float t_total;
+
+    // Physical pixels per unit of thickness in `screen space`.
+    float pixel_ratio;
 };
 
 readonly layout(std430, binding = 1) buffer Transform2DArray {
@@ -160,7 +163,7 @@ void main() {
         const vec2 complex = ab / len;
 
         const mat4 mx_l2s = MX_VIEWPORT * MX_PROJECTION * MX_VIEW * mx_l2w;
-        const float th_l = g.thickness >= 0 ? g.thickness / length(mx_l2s * vec4(1.0, 0.0, 0.0, 0.0)) : abs(g.thickness);
+        const float th_l = g.thickness >= 0 ? g.thickness * pixel_ratio / length(mx_l2s * vec4(1.0, 0.0, 0.0, 0.0)) : abs(g.thickness);
 
         const vec2 scale = vec2(len, th_l);
 
@@ -170,7 +173,8 @@ void main() {
         mx_g2l = to_matrix(g.extras.xy, g.extras.z, g.extras.w);
 
         const mat4 matrix = g.thickness >= 0 ? MX_VIEWPORT * MX_PROJECTION * MX_VIEW * mx_l2w * mx_g2l : mx_g2l;
-        const float th_g = abs(g.thickness) / length(matrix * vec4(normalize(v_pos.xy), 0.0, 0.0));
+        const float th_s = g.thickness >= 0 ? g.thickness * pixel_ratio : abs(g.thickness);
+        const float th_g = th_s / length(matrix * vec4(normalize(v_pos.xy), 0.0, 0.0));
         th = th_g;
     }
 
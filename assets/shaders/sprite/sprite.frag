@@ -0,0 +1,19 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(set = 1, binding = 0) uniform texture2D t_sprite;
+layout(set = 1, binding = 1) uniform sampler s_sprite;
+
+// NOTE: IN VARIABLES
+
+layout(location = 0) in vec2 uv;
+layout(location = 1) in vec4 color;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec4 o_target;
+
+void main() {
+    o_target = texture(sampler2D(t_sprite, s_sprite), uv) * color;
+}
@@ -0,0 +1,73 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(set = 0, binding = 0) uniform Common {
+    // Transform point from `world space` to `eye space`.
+    mat4 MX_VIEW;
+    // Transform point from `eye space` to `NDC`.
+    mat4 MX_PROJECTION;
+    // Transform point from `NDC` to `screen space`.
+    mat4 MX_VIEWPORT;
+
+    // Viewport size
+    vec2 vp_size;
+
+    // Delta time
+    float t_delta;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+// vertex
+layout(location = 0) in vec4 v_pos;
+// instance: Transform2D
+layout(location = 1) in vec2 i_position;
+layout(location = 2) in vec2 i_complex;
+layout(location = 3) in vec2 i_scale;
+// instance: (min_u, min_v, max_u, max_v)
+layout(location = 4) in vec4 i_uv_rect;
+// instance: pivot in `local space`, (0, 0) is the left-bottom corner.
+layout(location = 5) in vec2 i_pivot;
+// instance: tint color
+layout(location = 6) in uint i_color;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec2 uv;
+layout(location = 1) out vec4 color;
+
+// NOTE: FUNCTIONS AREA
+
+mat4 to_matrix(vec2 position, vec2 complex, vec2 scale) {
+    return mat4(
+        complex.x * scale.x, complex.y * scale.x, 0.0, 0.0,     // column 0
+        -complex.y * scale.y, complex.x * scale.y, 0.0, 0.0,    // column 1
+        0.0, 0.0, 1.0, 0.0,                                     // column 2
+        position.x, position.y, 0.0, 1.0                        // column 3
+    );
+}
+
+vec4 hex_to_color(uint hex) {
+    uint r = hex >> 24;
+    uint g = hex >> 16 & 0xFF;
+    uint b = hex >> 8 & 0xFF;
+    uint a = hex & 0xFF;
+
+    return vec4(r, g, b, a) / 255.0;
+}
+
+void main() {
+    // Quad vertex from [-0.5, 0.5] to [0.0, 1.0].
+    vec2 st = v_pos.xy + 0.5;
+
+    // The v axis of texture points down.
+    uv = vec2(mix(i_uv_rect.x, i_uv_rect.z, st.x), mix(i_uv_rect.w, i_uv_rect.y, st.y));
+    color = hex_to_color(i_color);
+
+    mat4 mx_l2w = to_matrix(i_position, i_complex, i_scale);
+
+    gl_Position = MX_PROJECTION * MX_VIEW * mx_l2w * vec4(st - i_pivot, 0.0, 1.0);
+}
@@ -42,8 +42,9 @@ fn main() -> Result<(), AppBuildError> {
         .add_thread_local_fn_destroy(thread_local_fn_destroy)
         // Convert to `AppBuilder` after finish building the `AppStage`.
         .into_app_builder()
-        // Consume the `AppBuilder` to build the `App`.
-        .build()
+        // Consume the `AppBuilder` to build the `App`, the `AppStage`s are sorted by their
+        // ordering constraints(see `AppStageBuilder::runs_before()`).
+        .build()?
         // Hijack the main thread to run the `App`.
         .run();
 
@@ -7,7 +7,7 @@ fn main() -> Result<(), AppBuildError> {
         .add_system_process(parallel_process_system())
         .add_thread_local_system_process(thread_local_process_system())
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
@@ -9,8 +9,11 @@ fn main() -> Result<(), AppBuildError> {
         // //                                              ^^^^^^^^^^^^^^^^^^^^^^+-------------+
         // //                                                                                  |
         // // Construct the system-local variable and move it into the system. <---------------+
+        // .set_fixed_rate(50.0)
+        // .add_system_fixed_update(println_fixed_time_system())
+        // // Run `println_fixed_time` 50 times per second whatever the framerate is.
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
@@ -50,3 +53,17 @@ fn diagnostic_time_cost(#[state] d_timer: &mut DiagnosticTimer)
 
     println!("{}", d_timer);
 }
+
+// Get `FixedTime` from `Resources`.
+//
+// `FixedTime` is used to record the time information of the fixed schedule of `AppStage`,
+// the systems in the fixed schedule should use `FixedTime::step()` instead of `Time::delta()`.
+#[system]
+fn println_fixed_time(#[resource] fixed_time: &FixedTime) {
+    println!(
+        "step: {:?}, step count: {}, alpha: {:.2}",
+        fixed_time.step(),
+        fixed_time.step_count(),
+        fixed_time.alpha()
+    );
+}
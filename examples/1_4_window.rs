@@ -1,19 +1,41 @@
 //! The `window` module is only in a usable state and will be gradually improved afterwawrds.
 
-use yam::legion::*;
+use yam::legion::{systems::CommandBuffer, *};
 use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
+    let window_desc = WindowDescriptor {
+        title: String::from("window"),
+        min_size: Some((320, 180)),
+        present_mode: PresentMode::Fifo,
+        ..Default::default()
+    };
+
     AppBuilder::new()
+        .with_window_descriptor(window_desc)
         .create_stage_builder(String::from("default"))?
+        .add_thread_local_system_startup(set_window_icon_system())
         .add_thread_local_system_process(control_fullscreen_system())
+        .add_thread_local_system_process(open_extra_window_system())
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
 }
 
+#[system]
+fn set_window_icon(#[resource] window: &mut Window, #[resource] asset_server: &mut AssetServer) {
+    let icon = asset_server
+        .load_texture("images/sprites/yam.png")
+        .expect("ERR: Failed to load texture.");
+
+    window
+        .set_window_icon(asset_server, Some(icon))
+        .expect("ERR: Failed to set window icon.");
+    window.set_cursor_icon(CursorIcon::Crosshair);
+}
+
 #[system]
 fn control_fullscreen(#[resource] window: &mut Window, #[resource] input: &Input) {
     if input.keyboard.just_pressed(KeyCode::F) {
@@ -24,3 +46,28 @@ fn control_fullscreen(#[resource] window: &mut Window, #[resource] input: &Input
         }
     }
 }
+
+#[system]
+fn open_extra_window(
+    cmd: &mut CommandBuffer,
+    #[resource] input: &Input,
+    #[resource] windows: &mut Windows,
+) {
+    if input.keyboard.just_pressed(KeyCode::N) {
+        windows.open(WindowDescriptor {
+            title: String::from("extra window"),
+            width: 480,
+            height: 270,
+            ..Default::default()
+        });
+    }
+
+    // NOTE: The extra window isn't drawn until a camera targets it.
+    for &id in windows.just_opened() {
+        cmd.push((
+            Transform2D::default(),
+            Camera2D::default(),
+            WindowTarget(id),
+        ));
+    }
+}
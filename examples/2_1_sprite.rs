@@ -7,10 +7,11 @@ fn main() -> Result<(), AppBuildError> {
         .create_stage_builder(String::from("default"))?
         .add_thread_local_system_startup(introduction_system())
         .add_thread_local_system_startup(init_entities_system())
+        .add_thread_local_system_startup(init_actions_system())
         .add_thread_local_system_process(control_camera_system())
         .add_thread_local_system_process(control_sprite_system())
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
@@ -20,20 +21,25 @@ fn main() -> Result<(), AppBuildError> {
 fn introduction() {
     println!("Introduction:");
     println!("  1. Pressed the middle button of mouse to move the camera.");
-    println!("  2. Pressed AWSD to move the sprite.");
+    println!("  2. Pressed AWSD or arrow keys to move the sprite.");
 }
 
 #[system]
-fn init_entities(commands: &mut CommandBuffer, #[resource] window: &Window) {
+fn init_entities(
+    commands: &mut CommandBuffer,
+    #[resource] window: &Window,
+    #[resource] asset_server: &mut AssetServer,
+) {
     let (width, height) = window.resolution();
 
+    let yam = asset_server
+        .load_texture("images/sprites/yam.png")
+        .expect("ERR: Failed to load texture.");
+
     // Push camera entity to `World`.
     commands.push((Transform2D::default(), Camera2D::new(width, height)));
     // Push sprite entity to `World`.
-    commands.push((
-        Transform2D::with_scale(64.0, 64.0),
-        Sprite { color: Rgba::RED },
-    ));
+    commands.push((Transform2D::with_scale(64.0, 64.0), Sprite::new(yam)));
 }
 
 #[system(for_each)]
@@ -51,24 +57,41 @@ fn control_camera(
     }
 }
 
+#[system]
+fn init_actions(#[resource] actions: &mut ActionMap) {
+    fn buttons(negative: KeyCode, positive: KeyCode) -> AxisBinding {
+        AxisBinding::Buttons {
+            negative: Binding::Key(negative),
+            positive: Binding::Key(positive),
+        }
+    }
+
+    actions.bind_axis2d(
+        "move",
+        Axis2DBinding {
+            x: buttons(KeyCode::A, KeyCode::D),
+            y: buttons(KeyCode::S, KeyCode::W),
+        },
+    );
+    actions.bind_axis2d(
+        "move",
+        Axis2DBinding {
+            x: buttons(KeyCode::Left, KeyCode::Right),
+            y: buttons(KeyCode::Down, KeyCode::Up),
+        },
+    );
+}
+
 #[system(for_each)]
 #[filter(component::<Sprite>())]
 fn control_sprite(
     transform2d: &mut Transform2D,
     #[resource] input: &Input,
+    #[resource] actions: &ActionMap,
     #[resource] time: &Time,
 ) {
     const MOVE_SPEED: f32 = 256.0;
 
-    if input.keyboard.pressed(KeyCode::A) {
-        transform2d.position -= Vector2::new(1.0, 0.0) * time.delta().as_secs_f32() * MOVE_SPEED;
-    } else if input.keyboard.pressed(KeyCode::D) {
-        transform2d.position += Vector2::new(1.0, 0.0) * time.delta().as_secs_f32() * MOVE_SPEED;
-    }
-
-    if input.keyboard.pressed(KeyCode::S) {
-        transform2d.position -= Vector2::new(0.0, 1.0) * time.delta().as_secs_f32() * MOVE_SPEED;
-    } else if input.keyboard.pressed(KeyCode::W) {
-        transform2d.position += Vector2::new(0.0, 1.0) * time.delta().as_secs_f32() * MOVE_SPEED;
-    }
+    let (x, y) = actions.value2d(input, "move");
+    transform2d.position += Vector2::new(x, y) * time.delta().as_secs_f32() * MOVE_SPEED;
 }
@@ -4,17 +4,32 @@ use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
     AppBuilder::new()
+        .add_plugin(CameraControlPlugin)?
         .create_stage_builder(String::from("default"))?
         .add_thread_local_system_startup(init_entities_system())
-        .add_thread_local_system_process(control_camera_system())
         .add_thread_local_system_process(control_geometry_system())
+        .add_thread_local_system_process(draw_grid_system())
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
 }
 
+// Package the camera control as a `Plugin`, so other apps can reuse it by `add_plugin()`.
+struct CameraControlPlugin;
+
+impl Plugin for CameraControlPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_stage(
+            AppStageBuilder::new(String::from("camera_control"))
+                .add_thread_local_system_process(control_camera_system()),
+        )?;
+
+        Ok(())
+    }
+}
+
 #[system]
 fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     let (width, height) = window.resolution();
@@ -186,6 +201,45 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 }
 
+/// Draw the grid in the view of camera with `DebugDraw`.
+#[system(for_each)]
+fn draw_grid(
+    transform2d: &Transform2D,
+    camera2d: &Camera2D,
+    #[resource] debug_draw: &mut DebugDraw,
+) {
+    const SIZE: f32 = 100.0;
+
+    let half_w = 0.5 * transform2d.scale.x * camera2d.width as f32;
+    let half_h = 0.5 * transform2d.scale.y * camera2d.height as f32;
+    let (cx, cy) = (transform2d.position.x, transform2d.position.y);
+
+    let (x0, x1) = ((cx - half_w) / SIZE, (cx + half_w) / SIZE);
+    let (y0, y1) = ((cy - half_h) / SIZE, (cy + half_h) / SIZE);
+
+    for x in (x0.floor() as i32)..=(x1.ceil() as i32) {
+        let x = x as f32 * SIZE;
+        debug_draw.line(
+            Vector2::new(x, 0.0),
+            Vector2::new(x, 1.0),
+            BorderDecoration::Dash,
+            Rgba::CAMEL,
+        );
+    }
+
+    for y in (y0.floor() as i32)..=(y1.ceil() as i32) {
+        let y = y as f32 * SIZE;
+        debug_draw.line(
+            Vector2::new(0.0, y),
+            Vector2::new(1.0, y),
+            BorderDecoration::Dash,
+            Rgba::CAMEL,
+        );
+    }
+
+    debug_draw.text_marker(Vector2::new(0.0, 0.0), "origin", 16.0, Rgba::SOFT_BLACK);
+}
+
 #[system(for_each)]
 #[filter(component::<Camera2D>())]
 fn control_camera(transform: &mut Transform2D, #[resource] input: &Input) {
@@ -198,11 +252,20 @@ fn control_camera(transform: &mut Transform2D, #[resource] input: &Input) {
         transform.position -= Vector2::<f32>::new(dx, -dy) * transform.scale.x;
     }
 
-    let (_, motion) = input.mouse.mouse_wheel_motion();
+    if let Some((dx, dy)) = input.touches.drag() {
+        transform.position -= Vector2::<f32>::new(dx, -dy) * transform.scale.x;
+    }
+
+    let (_, motion) = input.mouse.mouse_wheel_scroll();
     transform.scale = Vector2::new(
         (transform.scale.x + motion * SSPEED).max(0.2),
         (transform.scale.y + motion * SSPEED).max(0.2),
     );
+
+    // NOTE: Pinch out to zoom in.
+    if let Some(scale) = input.touches.pinch() {
+        transform.scale = (transform.scale / scale).map(|s| s.max(0.2));
+    }
 }
 
 #[system(for_each)]
@@ -15,7 +15,7 @@ fn main() -> Result<(), AppBuildError> {
         .add_thread_local_system_process(control_camera_system())
         .add_thread_local_system_process(wander_system())
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
@@ -29,7 +29,11 @@ fn introduction() {
 }
 
 #[system]
-fn init_entities(commands: &mut CommandBuffer, #[resource] window: &Window) {
+fn init_entities(
+    commands: &mut CommandBuffer,
+    #[resource] window: &Window,
+    #[resource] asset_server: &mut AssetServer,
+) {
     const SPRITE_SIZE: f32 = 8.0;
 
     const SQRT_COUNT: usize = 1_024;
@@ -37,6 +41,9 @@ fn init_entities(commands: &mut CommandBuffer, #[resource] window: &Window) {
 
     let (width, height) = window.resolution();
 
+    // All sprites share the same white texture, tinted by the color of sprite.
+    let white = asset_server.add_texture(Texture::from_color(1, 1, Rgba::WHITE));
+
     // Push camera entity to `World`.
     commands.push((Transform2D::default(), Camera2D::new(width, height)));
 
@@ -57,9 +64,7 @@ fn init_entities(commands: &mut CommandBuffer, #[resource] window: &Window) {
     commands.push((
         transform2ds,
         steerings,
-        Sprite {
-            color: Rgba::ORANGE,
-        },
+        Sprite::with_color(white, Rgba::ORANGE),
     ));
 }
 
@@ -75,7 +80,7 @@ fn control_camera(transform: &mut Transform2D, #[resource] input: &Input) {
         transform.position += Vector2::<f32>::new(dx, -dy) * TSPEED;
     }
 
-    let (_, motion) = input.mouse.mouse_wheel_motion();
+    let (_, motion) = input.mouse.mouse_wheel_scroll();
     transform.scale = Vector2::new(
         (transform.scale.x + motion).max(0.2),
         (transform.scale.y + motion).max(0.2),
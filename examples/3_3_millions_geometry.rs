@@ -14,7 +14,7 @@ fn main() -> Result<(), AppBuildError> {
         .add_thread_local_system_process(control_camera_system())
         .add_thread_local_system_process(wander_system(0.0, 64.0, 16.0))
         .into_app_builder()
-        .build()
+        .build()?
         .run();
 
     Ok(())
@@ -89,7 +89,7 @@ fn control_camera(transform: &mut Transform2D, #[resource] input: &Input) {
         transform.position -= Vector2::<f32>::new(dx, dy);
     }
 
-    let (_, motion) = input.mouse.mouse_wheel_motion();
+    let (_, motion) = input.mouse.mouse_wheel_scroll();
     transform.scale = Vector2::new(
         (transform.scale.x + motion * SSPEED).max(0.1),
         (transform.scale.y + motion * SSPEED).max(0.1),
@@ -1,48 +1,82 @@
 use crate::{
-    components::time::Time,
-    input::Input,
+    asset::AssetPlugin,
+    components::{
+        hierarchy::TransformPlugin,
+        time::{FixedTime, Time, MAX_TIME_SCALE},
+    },
+    events::{EventChannel, Events},
+    input::{Input, InputEvent, InputPlugin, InputRecorder},
     legion::{
         systems::{Builder, ParallelRunnable, Runnable},
         Resources, Schedule, World,
     },
     misc::coordinates::Transformation,
-    render::create_app_stage_render,
-    window::Window,
-    DEFAULT_HEIGHT, DEFAULT_WIDTH,
+    plugin::{Plugin, ResourceInit, SystemStageInit},
+    render::{DebugDraw, DebugDrawPlugin, RenderPlugin},
+    state::{AppState, AppStateBuilder, State, StateEvent, StateMachine},
+    task::{TaskPlugin, TaskPool},
+    window::{Window, WindowDescriptor, WindowPlugin, WindowResized, Windows},
 };
 
 use winit::{
-    dpi::LogicalSize,
-    event::{DeviceEvent, Event, StartCause, WindowEvent},
+    event::{Event, StartCause, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
 };
 
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell},
     fmt,
     rc::Rc,
     slice::{Iter, IterMut},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
 #[derive(Default, Debug)]
 pub struct App {
     busy_stages: Vec<AppStage>,
+    window_desc: WindowDescriptor,
+
+    states: Vec<State>,
+    initial_state: Option<String>,
+
+    event_channels: Vec<EventChannel>,
+    resources: Vec<ResourceInit>,
+    system_stages: Vec<SystemStageInit>,
 }
 
 impl App {
+    /// Create an empty `App`.
+    ///
+    /// NOTE: It has none of the default plugins added by `AppBuilder::new()`, like `RenderPlugin`.
     pub fn new() -> Self {
         Self {
             busy_stages: Default::default(),
+            window_desc: Default::default(),
+
+            states: Default::default(),
+            initial_state: None,
+
+            event_channels: Default::default(),
+            resources: Default::default(),
+            system_stages: Default::default(),
         }
     }
 
     pub fn from_stages(stages: Vec<AppStage>) -> Self {
         Self {
             busy_stages: stages,
+            ..Self::new()
         }
     }
 
+    /// Set the settings to create the window by `Self::run()`.
+    pub fn with_window_descriptor(mut self, window_desc: WindowDescriptor) -> Self {
+        self.window_desc = window_desc;
+        self
+    }
+
     /// Hijack the main thread to run the `App`.
     ///
     /// # Panics
@@ -50,32 +84,34 @@ impl App {
     /// Panics if the ownership of `AppSettings` moved to outer.
     pub fn run(self) {
         let event_loop = EventLoop::new();
-        let window = Window::new(
-            WindowBuilder::new()
-                .with_title("default")
-                .with_inner_size(LogicalSize::<u32>::from((DEFAULT_WIDTH, DEFAULT_HEIGHT)))
-                .build(&event_loop)
-                .unwrap(),
-        );
-
-        let busy_stages = Rc::new(RefCell::new(self.busy_stages));
-
-        // FIXME: Place the render `AppStage` to right place to prevent removal in accident.
-        busy_stages
-            .borrow_mut()
-            .push(create_app_stage_render(&window));
+        let window = Window::new(&self.window_desc, &event_loop);
+        let primary_id = window.id();
 
         let mut world = World::default();
         let mut resources = Resources::default();
 
-        resources.insert::<Input>(Input::new());
-        resources.insert::<AppSettings>(AppSettings::new(&busy_stages));
+        // NOTE: The window is inserted first, `RenderPlugin` creates `default_render` by it.
         resources.insert::<Window>(window);
+        let (busy_stages, mut state_machine, event_channels) = self.setup(&mut resources);
+
+        if let Some(mut input) = resources.get_mut::<Input>() {
+            // NOTE: The gamepad backend wakes up the loop by an empty user event.
+            let proxy = Mutex::new(event_loop.create_proxy());
+            input.gamepads.set_waker(Arc::new(move || {
+                let _ = proxy
+                    .lock()
+                    .expect("ERR: The event loop proxy is poisoned.")
+                    .send_event(());
+            }));
+        }
 
         // local datas
-        let mut input_evts: Vec<Event<'static, ()>> = Default::default();
+        let mut input_evts: Vec<InputEvent> = Default::default();
+        let mut resized_evts: Vec<WindowResized> = Default::default();
+        let mut failed_cmds: Vec<AppCommandFailed> = Default::default();
+        let mut pacer = FramePacer::new();
 
-        event_loop.run(move |event, _, control_flow| {
+        event_loop.run(move |event, target, control_flow| {
             match event {
                 Event::NewEvents(sc) => match sc {
                     StartCause::Init => {
@@ -84,71 +120,159 @@ impl App {
                             stage.init(&mut world, &mut resources);
                         }
                     }
-                    StartCause::Poll => {
+                    StartCause::Poll
+                    | StartCause::ResumeTimeReached { .. }
+                    | StartCause::WaitCancelled { .. } => {
+                        let now = Instant::now();
+                        let (target_fps, low_power) = {
+                            let settings = resources.get::<AppSettings>().unwrap();
+
+                            (settings.target_fps(), settings.is_low_power())
+                        };
+
+                        // NOTE: Woken up by the events too early, wait for the next frame.
+                        if !pacer.is_ready(now, low_power) {
+                            return;
+                        }
+                        pacer.advance(now, target_fps);
+
                         // NOTE: apply app_settings added by last frame, if user try to exit, then exit.
-                        if !resources.get_mut::<AppSettings>().unwrap().apply() {
-                            // execute all stages that in work state.
-                            for stage in RefCell::borrow(&busy_stages).iter() {
-                                stage.play(&mut world, &mut resources);
-                            }
-                        } else {
+                        if resources
+                            .get_mut::<AppSettings>()
+                            .unwrap()
+                            .apply(&mut failed_cmds)
+                        {
                             *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                        for channel in event_channels.iter() {
+                            channel.update(&resources);
+                        }
+                        resources
+                            .get_mut::<Events<AppCommandFailed>>()
+                            .unwrap()
+                            .send_batch(failed_cmds.drain(..));
+                        state_machine.apply(&mut world, &mut resources, &busy_stages);
+                        Self::send_stage_events(&resources);
+                        TaskPool::flush(&mut world, &mut resources);
+
+                        // NOTE: The events are accumulated between frames, so the frames skipped
+                        // by the frame limiter don't lose any `just_pressed`.
+                        Self::apply_input(&mut resources, &mut input_evts);
+                        match resources.get_mut::<Events<WindowResized>>() {
+                            Some(mut events) => events.send_batch(resized_evts.drain(..)),
+                            None => resized_evts.clear(),
+                        }
+
+                        // NOTE: Apply the input of extra windows, and open the requested windows.
+                        if let Some(mut windows) = resources.get_mut::<Windows>() {
+                            windows.maintain(target);
+                        }
+
+                        state_machine.update(&mut world, &mut resources);
+
+                        // execute all stages that in work state.
+                        for stage in RefCell::borrow(&busy_stages).iter() {
+                            stage.play(&mut world, &mut resources);
                         }
                     }
-                    _ => {}
                 },
                 Event::WindowEvent {
-                    event: ref wevt, ..
+                    window_id,
+                    event: wevt,
+                } if window_id != primary_id => {
+                    // NOTE: The extra windows are opened by `Windows` only.
+                    let mut windows = match resources.get_mut::<Windows>() {
+                        Some(windows) => windows,
+                        None => return,
+                    };
+
+                    match wevt {
+                        WindowEvent::CloseRequested => {
+                            windows.close(window_id);
+                            pacer.request_frame();
+                        }
+                        _ => {
+                            if let WindowEvent::Resized(size) = wevt {
+                                resized_evts.push(WindowResized {
+                                    id: window_id,
+                                    width: size.width,
+                                    height: size.height,
+                                });
+                            }
+
+                            if let Some(ievt) = InputEvent::from_window_event(&wevt) {
+                                windows.push_input_event(window_id, ievt);
+                                pacer.request_frame();
+                            }
+                        }
+                    }
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: wevt,
                 } => match wevt {
                     WindowEvent::CloseRequested => {
                         *control_flow = ControlFlow::Exit;
                     }
-                    WindowEvent::Resized(_) | WindowEvent::Moved(_) => {
-                        // NOTE: To prevent input freeze bug
-                        resources.get_mut::<Input>().unwrap().release_all();
-                    }
-                    WindowEvent::ModifiersChanged(_) => {}
-
-                    WindowEvent::MouseInput { .. }
-                    | WindowEvent::MouseWheel { .. }
-                    | WindowEvent::CursorMoved { .. }
-                    | WindowEvent::CursorEntered { .. }
-                    | WindowEvent::CursorLeft { .. }
-                    | WindowEvent::KeyboardInput { .. } => {
-                        input_evts.push(event.to_static().unwrap());
+                    WindowEvent::ScaleFactorChanged { .. } => {
+                        // NOTE: Accept the size suggested by the system, the render follows the
+                        // new resolution in next frame.
                     }
+                    _ => {
+                        if let WindowEvent::Resized(size) = wevt {
+                            resized_evts.push(WindowResized {
+                                id: window_id,
+                                width: size.width,
+                                height: size.height,
+                            });
+                        }
 
-                    WindowEvent::ScaleFactorChanged { .. } => {
-                        todo!()
+                        // NOTE: `Resized` and `Moved` release all buttons to prevent input freeze bug.
+                        if let Some(ievt) = InputEvent::from_window_event(&wevt) {
+                            input_evts.push(ievt);
+                        }
                     }
-                    _ => {}
                 },
-                Event::DeviceEvent {
-                    event: ref devt, ..
-                } => match devt {
-                    DeviceEvent::MouseMotion { .. } => {
-                        input_evts.push(event.to_static().unwrap());
+                Event::DeviceEvent { event: devt, .. } => {
+                    if let Some(ievt) = InputEvent::from_device_event(&devt) {
+                        input_evts.push(ievt);
                     }
-                    _ => {}
-                },
+                }
                 Event::Suspended => {
-                    // TODO: for specific platform(like android, iphone)
-                    todo!()
+                    // NOTE: The app may not receive the release events when it's in background.
+                    input_evts.push(InputEvent::ReleaseAll);
                 }
                 Event::Resumed => {
-                    // TODO: for specific platform(like android, iphone)
-                    todo!()
+                    // TODO: Recreate the surface for specific platform(like android).
                 }
                 Event::MainEventsCleared => {
-                    let trf = *resources.get_or_default::<Transformation>();
-                    resources
-                        .get_mut::<Input>()
-                        .unwrap()
-                        .apply(&mut input_evts, &trf)
+                    // NOTE: The loop is woken up by the gamepad backend, see
+                    // `GamepadBackend::set_waker()`.
+                    Self::poll_gamepads(&resources, &mut input_evts);
+
+                    let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+                    if !input_evts.is_empty()
+                        || settings.take_redraw_request()
+                        || settings.has_commands()
+                        || matches!(resources.get::<InputRecorder>(), Some(recorder) if recorder.is_playing())
+                    {
+                        pacer.request_frame();
+                    }
+
+                    if *control_flow != ControlFlow::Exit {
+                        *control_flow = pacer.control_flow(Instant::now(), settings.is_low_power());
+                    }
+                }
+                Event::RedrawRequested(_) => {
+                    // NOTE: The system asks to redraw, e.g. the window is uncovered.
+                    pacer.request_frame();
                 }
-                Event::RedrawRequested(_) => {}
                 Event::RedrawEventsCleared => {}
                 Event::LoopDestroyed => {
+                    state_machine.exit_all(&mut world, &mut resources, &busy_stages);
+
                     // NOTE: destroy all AppStages
                     for stage in RefCell::borrow(&busy_stages).iter() {
                         stage.free(&mut world, &mut resources);
@@ -158,32 +282,402 @@ impl App {
             }
         });
     }
+
+    /// Run the `App` on the current thread without a window or a GPU.
+    ///
+    /// The `startup`, `process` and `destroy` schedules of all `AppStage`s are executed as in
+    /// `Self::run()`, but the `default_render` stage is never created and `Input` stays empty
+    /// unless an `InputRecording` is played by `InputRecorder` or a `GamepadBackend` is set.
+    /// It's useful for testing the logic of stages and running simulation servers.
+    ///
+    /// The frame limiter of `AppSettings` is respected, but the low power mode is ignored.
+    pub fn run_headless(self, mode: HeadlessMode) {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let (busy_stages, mut state_machine, event_channels) = self.setup(&mut resources);
+
+        // NOTE: init all AppStages
+        for stage in RefCell::borrow(&busy_stages).iter() {
+            stage.init(&mut world, &mut resources);
+        }
+
+        let mut frame_count: u64 = 0;
+        let mut input_evts: Vec<InputEvent> = Default::default();
+        let mut failed_cmds: Vec<AppCommandFailed> = Default::default();
+        let mut pacer = FramePacer::new();
+
+        loop {
+            if let HeadlessMode::Frames(frames) = mode {
+                if frame_count >= frames {
+                    break;
+                }
+            }
+
+            // NOTE: Nothing wakes up a headless app, so only the frame limiter is respected.
+            let now = Instant::now();
+            if let ControlFlow::WaitUntil(next_frame) = pacer.control_flow(now, false) {
+                thread::sleep(next_frame - now);
+            }
+            let target_fps = resources.get::<AppSettings>().unwrap().target_fps();
+            pacer.advance(Instant::now(), target_fps);
+
+            // NOTE: apply app_settings added by last frame, if user try to exit, then exit.
+            if resources
+                .get_mut::<AppSettings>()
+                .unwrap()
+                .apply(&mut failed_cmds)
+            {
+                break;
+            }
+            for channel in event_channels.iter() {
+                channel.update(&resources);
+            }
+            resources
+                .get_mut::<Events<AppCommandFailed>>()
+                .unwrap()
+                .send_batch(failed_cmds.drain(..));
+            state_machine.apply(&mut world, &mut resources, &busy_stages);
+            Self::send_stage_events(&resources);
+            TaskPool::flush(&mut world, &mut resources);
+
+            Self::poll_gamepads(&resources, &mut input_evts);
+            Self::apply_input(&mut resources, &mut input_evts);
+
+            state_machine.update(&mut world, &mut resources);
+
+            // execute all stages that in work state.
+            for stage in RefCell::borrow(&busy_stages).iter() {
+                stage.play(&mut world, &mut resources);
+            }
+
+            // NOTE: Nothing draws the shapes of `DebugDraw`, drop them.
+            if let Some(mut debug_draw) = resources.get_mut::<DebugDraw>() {
+                debug_draw.clear();
+            }
+
+            frame_count += 1;
+        }
+
+        state_machine.exit_all(&mut world, &mut resources, &busy_stages);
+
+        // NOTE: destroy all AppStages
+        for stage in RefCell::borrow(&busy_stages).iter() {
+            stage.free(&mut world, &mut resources);
+        }
+    }
+}
+
+impl App {
+    /// Insert the resources, and create the system stages and `AppState` declared by the plugins.
+    fn setup(
+        self,
+        resources: &mut Resources,
+    ) -> (Rc<RefCell<Vec<AppStage>>>, StateMachine, Vec<EventChannel>) {
+        for resource in self.resources {
+            resource.insert(resources);
+        }
+
+        // NOTE: The system stages always run after the stages of user, and can't be removed.
+        let mut busy_stages = self.busy_stages;
+        busy_stages.extend(
+            self.system_stages
+                .into_iter()
+                .filter_map(|init| init.create(resources)),
+        );
+        let busy_stages = Rc::new(RefCell::new(busy_stages));
+
+        let mut settings = AppSettings::new(&busy_stages);
+        let state_machine = StateMachine::new(self.states, &mut settings);
+        // NOTE: The stages of inactive states never work, don't publish them as rested.
+        settings.take_stage_events();
+        resources.insert::<AppState>(AppState::new(state_machine.names(), self.initial_state));
+        resources.insert::<AppSettings>(settings);
+        let event_channels = Self::insert_event_channels(self.event_channels, resources);
+
+        (busy_stages, state_machine, event_channels)
+    }
+
+    /// Apply the input events to `Input`, then send them by `Events<InputEvent>`.
+    ///
+    /// NOTE: The events are dropped without `InputPlugin`.
+    fn apply_input(resources: &mut Resources, input_evts: &mut Vec<InputEvent>) {
+        let trf = *resources.get_or_default::<Transformation>();
+
+        if let Some(mut recorder) = resources.get_mut::<InputRecorder>() {
+            recorder.process(input_evts);
+        }
+        if let Some(mut input) = resources.get_mut::<Input>() {
+            input.apply(input_evts, &trf);
+        }
+        match resources.get_mut::<Events<InputEvent>>() {
+            Some(mut events) => events.send_batch(input_evts.drain(..)),
+            None => input_evts.clear(),
+        }
+    }
+
+    fn send_stage_events(resources: &Resources) {
+        let stage_evts = resources
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .take_stage_events();
+
+        resources
+            .get_mut::<Events<StageEvent>>()
+            .unwrap()
+            .send_batch(stage_evts);
+    }
+
+    fn poll_gamepads(resources: &Resources, input_evts: &mut Vec<InputEvent>) {
+        if let Some(mut input) = resources.get_mut::<Input>() {
+            input.gamepads.poll(input_evts);
+        }
+    }
+
+    /// Insert the registered and the built-in `Events<T>`.
+    fn insert_event_channels(
+        mut channels: Vec<EventChannel>,
+        resources: &mut Resources,
+    ) -> Vec<EventChannel> {
+        let mut insert_builtin = |channel: EventChannel| {
+            if !channels
+                .iter()
+                .any(|other| other.type_id() == channel.type_id())
+            {
+                channels.push(channel);
+            }
+        };
+        insert_builtin(EventChannel::of::<StateEvent>());
+        insert_builtin(EventChannel::of::<StageEvent>());
+        insert_builtin(EventChannel::of::<AppCommandFailed>());
+
+        for channel in channels.iter() {
+            channel.insert(resources);
+        }
+
+        channels
+    }
+}
+
+/// Decide when `App::run_headless()` stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessMode {
+    /// Run the given number of frames, or less if `AppSettings::quit()` is called.
+    Frames(u64),
+    /// Run until `AppSettings::quit()` is called.
+    UntilQuit,
 }
 
-#[derive(Default)]
 pub struct AppBuilder {
     stage_builders: Vec<AppStageBuilder>,
+    window_desc: WindowDescriptor,
+
+    state_builders: Vec<AppStateBuilder>,
+    initial_state: Option<String>,
+
+    event_channels: Vec<EventChannel>,
+    resources: Vec<ResourceInit>,
+    system_stages: Vec<SystemStageInit>,
+
+    plugins: Vec<Box<dyn Plugin>>,
+    // the names of all plugins added, including the built ones.
+    plugin_names: Vec<String>,
+    disabled_plugins: Vec<String>,
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AppBuilder {
+    /// Create the builder with the default plugins, which can be disabled by
+    /// `Self::disable_plugin_of()`:
+    ///
+    /// - `TransformPlugin`: the system stage `default_transform`.
+    /// - `RenderPlugin`: the system stage `default_render`, which is not created by
+    ///   `App::run_headless()`.
+    /// - `DebugDrawPlugin`: `DebugDraw`.
+    /// - `InputPlugin`: `Input`, `ActionMap`, `InputRecorder` and `Events<InputEvent>`.
+    /// - `WindowPlugin`: `Windows` and `Events<WindowResized>`.
+    /// - `AssetPlugin`: `AssetServer`.
+    /// - `TaskPlugin`: `TaskPool`.
     pub fn new() -> Self {
-        Self {
+        let mut app_builder = Self {
             stage_builders: Default::default(),
+            window_desc: Default::default(),
+
+            state_builders: Default::default(),
+            initial_state: None,
+
+            event_channels: Default::default(),
+            resources: Default::default(),
+            system_stages: Default::default(),
+
+            plugins: Default::default(),
+            plugin_names: Default::default(),
+            disabled_plugins: Default::default(),
+        };
+
+        app_builder
+            .insert_plugin(TransformPlugin)
+            .and_then(|app| app.insert_plugin(RenderPlugin))
+            .and_then(|app| app.insert_plugin(DebugDrawPlugin))
+            .and_then(|app| app.insert_plugin(InputPlugin))
+            .and_then(|app| app.insert_plugin(WindowPlugin))
+            .and_then(|app| app.insert_plugin(AssetPlugin))
+            .and_then(|app| app.insert_plugin(TaskPlugin))
+            .expect("ERR: Failed to add the default plugins.");
+
+        app_builder
+    }
+
+    /// Add the plugin, it's built by `Self::build()` after the plugins it depends on.
+    pub fn add_plugin<P: Plugin>(mut self, plugin: P) -> Result<Self, AppBuildError> {
+        self.insert_plugin(plugin)?;
+
+        Ok(self)
+    }
+
+    /// Skip building the plugin named `plugin_name`, e.g. disable a plugin added by another one.
+    pub fn disable_plugin(mut self, plugin_name: &str) -> Self {
+        self.disabled_plugins.push(String::from(plugin_name));
+        self
+    }
+
+    /// Skip building the plugin of type `P` whose `Plugin::name()` is not overridden, e.g. the
+    /// default plugins.
+    ///
+    /// ```
+    /// use yam::*;
+    ///
+    /// // NOTE: A server doesn't need the render and the window.
+    /// let app = AppBuilder::new()
+    ///     .disable_plugin_of::<RenderPlugin>()
+    ///     .disable_plugin_of::<WindowPlugin>()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn disable_plugin_of<P: Plugin>(self) -> Self {
+        self.disable_plugin(std::any::type_name::<P>())
+    }
+
+    /// Register the resource `Events<T>`, which is updated at the start of every frame.
+    ///
+    /// NOTE: `Events<StateEvent>`, `Events<StageEvent>` and `Events<AppCommandFailed>` are
+    /// registered always, `Events<InputEvent>` and `Events<WindowResized>` are registered by the
+    /// default plugins.
+    pub fn add_event<T: Send + Sync + 'static>(mut self) -> Self {
+        self.insert_event::<T>();
+        self
+    }
+
+    /// Insert the resource when the `App` runs, it replaces the resource of `yam` with the
+    /// same type.
+    pub fn add_resource<T: 'static>(mut self, resource: T) -> Self {
+        self.insert_resource(resource);
+        self
+    }
+
+    /// The same as `Self::add_plugin()`, used in `Plugin::build()`.
+    pub fn insert_plugin<P: Plugin>(&mut self, plugin: P) -> Result<&mut Self, AppBuildError> {
+        if self.plugin_names.iter().any(|name| name == plugin.name()) {
+            Err(AppBuildError::DuplicatePlugin(String::from(plugin.name())))
+        } else {
+            self.plugin_names.push(String::from(plugin.name()));
+            self.plugins.push(Box::new(plugin));
+            Ok(self)
         }
     }
 
-    pub fn add_stage_builder(
-        mut self,
+    /// The same as `Self::add_stage_builder()`, used in `Plugin::build()`.
+    pub fn insert_stage(
+        &mut self,
         stage_builder: AppStageBuilder,
-    ) -> Result<Self, AppBuildError> {
+    ) -> Result<&mut Self, AppBuildError> {
         if self.has_stage(stage_builder.name()) {
-            Err(AppBuildError::DuplicateName(stage_builder))
+            Err(AppBuildError::DuplicateName(Box::new(stage_builder)))
         } else {
             self.stage_builders.push(stage_builder);
             Ok(self)
         }
     }
 
+    /// The same as `Self::add_state()`, used in `Plugin::build()`.
+    pub fn insert_state(
+        &mut self,
+        state_builder: AppStateBuilder,
+    ) -> Result<&mut Self, AppBuildError> {
+        if self
+            .state_builders
+            .iter()
+            .any(|state| state.name() == state_builder.name())
+        {
+            Err(AppBuildError::DuplicateStateName(Box::new(state_builder)))
+        } else {
+            self.state_builders.push(state_builder);
+            Ok(self)
+        }
+    }
+
+    /// The same as `Self::add_event()`, used in `Plugin::build()`.
+    pub fn insert_event<T: Send + Sync + 'static>(&mut self) -> &mut Self {
+        let channel = EventChannel::of::<T>();
+
+        if !self
+            .event_channels
+            .iter()
+            .any(|other| other.type_id() == channel.type_id())
+        {
+            self.event_channels.push(channel);
+        }
+
+        self
+    }
+
+    /// The same as `Self::add_resource()`, used in `Plugin::build()`.
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) -> &mut Self {
+        self.resources.push(ResourceInit::new(resource));
+        self
+    }
+
+    /// Insert the resource created by `init` when the `App` runs, `init` can read the resources
+    /// inserted before, e.g. `Window` which only exists in `App::run()`.
+    pub fn insert_resource_with<T, F>(&mut self, init: F) -> &mut Self
+    where
+        T: 'static,
+        F: FnOnce(&Resources) -> T + 'static,
+    {
+        self.resources.push(ResourceInit::with(init));
+        self
+    }
+
+    /// Add the system stage created by `init` when the `App` runs, it runs after the stages of
+    /// user and can't be removed, skipped if `init` returns `None`.
+    pub(crate) fn insert_system_stage<F>(&mut self, init: F) -> &mut Self
+    where
+        F: FnOnce(&Resources) -> Option<AppStage> + 'static,
+    {
+        self.system_stages.push(SystemStageInit::new(init));
+        self
+    }
+
+    /// Set the settings to create the window, e.g. loaded by `WindowDescriptor::load()`.
+    pub fn with_window_descriptor(mut self, window_desc: WindowDescriptor) -> Self {
+        self.window_desc = window_desc;
+        self
+    }
+
+    pub fn add_stage_builder(
+        mut self,
+        stage_builder: AppStageBuilder,
+    ) -> Result<Self, AppBuildError> {
+        self.insert_stage(stage_builder)?;
+
+        Ok(self)
+    }
+
     pub fn create_stage_builder(
         self,
         stage_name: String,
@@ -191,20 +685,204 @@ impl AppBuilder {
         let mut stage_builder = AppStageBuilder::new(stage_name);
 
         if self.has_stage(stage_builder.name()) {
-            Err(AppBuildError::DuplicateName(stage_builder))
+            Err(AppBuildError::DuplicateName(Box::new(stage_builder)))
         } else {
             stage_builder.app_builder.replace(self);
             Ok(stage_builder)
         }
     }
 
-    pub fn build(self) -> App {
-        App::from_stages(
-            self.stage_builders
-                .into_iter()
-                .map(|stage_builder| stage_builder.build())
-                .collect(),
-        )
+    /// Add a state of `AppState`, its stages must be added to the `AppBuilder` too.
+    pub fn add_state(mut self, state_builder: AppStateBuilder) -> Result<Self, AppBuildError> {
+        self.insert_state(state_builder)?;
+
+        Ok(self)
+    }
+
+    /// Set the state entered at the first frame.
+    pub fn with_initial_state(mut self, state_name: &str) -> Self {
+        self.initial_state = Some(String::from(state_name));
+        self
+    }
+
+    /// Build the `App`, the stages are sorted by their ordering constraints.
+    ///
+    /// The stages without constraints between them keep the order they were added.
+    pub fn build(mut self) -> Result<App, AppBuildError> {
+        // NOTE: The resources of user replace the ones of plugins with the same type.
+        let resources = std::mem::take(&mut self.resources);
+        self.build_plugins()?;
+        self.resources.extend(resources);
+        self.check_states()?;
+
+        let order = Self::sort_stages(&self.stage_builders)?;
+
+        let mut stage_builders: Vec<Option<AppStageBuilder>> =
+            self.stage_builders.into_iter().map(Some).collect();
+        let stages = order
+            .into_iter()
+            .enumerate()
+            .map(|(order, index)| {
+                let mut stage = stage_builders[index].take().unwrap().build();
+                stage.order = Some(order);
+
+                stage
+            })
+            .collect();
+
+        let mut app = App::from_stages(stages).with_window_descriptor(self.window_desc);
+        app.states = self
+            .state_builders
+            .into_iter()
+            .map(|state_builder| state_builder.build())
+            .collect();
+        app.initial_state = self.initial_state;
+        app.event_channels = self.event_channels;
+        app.resources = self.resources;
+        app.system_stages = self.system_stages;
+
+        Ok(app)
+    }
+
+    /// Build the enabled plugins, the dependencies first.
+    fn build_plugins(&mut self) -> Result<(), AppBuildError> {
+        let mut built: Vec<String> = Vec::new();
+        let mut pending: Vec<Box<dyn Plugin>> = Vec::new();
+
+        loop {
+            // NOTE: The plugins may add other plugins when building.
+            let disabled_plugins = &self.disabled_plugins;
+            pending.extend(
+                self.plugins
+                    .drain(..)
+                    .filter(|plugin| !disabled_plugins.iter().any(|name| name == plugin.name())),
+            );
+
+            let ready = pending.iter().position(|plugin| {
+                plugin
+                    .dependencies()
+                    .iter()
+                    .all(|dependency| built.iter().any(|name| name == dependency))
+            });
+
+            if let Some(index) = ready {
+                let plugin = pending.remove(index);
+                plugin.build(self)?;
+
+                built.push(String::from(plugin.name()));
+            } else if pending.is_empty() {
+                return Ok(());
+            } else {
+                for plugin in pending.iter() {
+                    for dependency in plugin.dependencies() {
+                        if !built
+                            .iter()
+                            .map(String::as_str)
+                            .chain(pending.iter().map(|plugin| plugin.name()))
+                            .any(|name| name == dependency)
+                        {
+                            return Err(AppBuildError::PluginNotExist {
+                                plugin: String::from(plugin.name()),
+                                dependency: String::from(dependency),
+                            });
+                        }
+                    }
+                }
+
+                return Err(AppBuildError::CyclicPlugins(
+                    pending
+                        .iter()
+                        .map(|plugin| String::from(plugin.name()))
+                        .collect(),
+                ));
+            }
+        }
+    }
+
+    fn check_states(&self) -> Result<(), AppBuildError> {
+        if let Some(initial_state) = self.initial_state.as_ref() {
+            if !self
+                .state_builders
+                .iter()
+                .any(|state| state.name() == initial_state)
+            {
+                return Err(AppBuildError::StateNotExist(initial_state.clone()));
+            }
+        }
+
+        let mut stage_names = Vec::new();
+        for stage_name in self
+            .state_builders
+            .iter()
+            .flat_map(|state| state.stages().iter())
+        {
+            if !self.has_stage(stage_name) {
+                return Err(AppBuildError::StageNotExist(stage_name.clone()));
+            } else if stage_names.contains(&stage_name) {
+                return Err(AppBuildError::StageInMultipleStates(stage_name.clone()));
+            }
+
+            stage_names.push(stage_name);
+        }
+
+        Ok(())
+    }
+
+    /// Sort the stages topologically, return the indices of the stages in execution order.
+    fn sort_stages(stage_builders: &[AppStageBuilder]) -> Result<Vec<usize>, AppBuildError> {
+        let count = stage_builders.len();
+
+        // The stages named `name`, or in the group `name`.
+        let targets = |name: &str| -> Vec<usize> {
+            (0..count)
+                .filter(|&index| {
+                    let stage = &stage_builders[index];
+
+                    stage.name() == name || stage.groups.iter().any(|group| group == name)
+                })
+                .collect()
+        };
+
+        // edges[i] contains the stages which run after the stage `i`.
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for (index, stage) in stage_builders.iter().enumerate() {
+            for name in stage.before.iter() {
+                edges[index].extend(targets(name).into_iter().filter(|&to| to != index));
+            }
+            for name in stage.after.iter() {
+                for from in targets(name).into_iter().filter(|&from| from != index) {
+                    edges[from].push(index);
+                }
+            }
+        }
+
+        let mut in_degrees = vec![0; count];
+        for to in edges.iter().flatten() {
+            in_degrees[*to] += 1;
+        }
+
+        // NOTE: Always pick the earliest added stage which is ready, to keep the order stable.
+        let mut order = Vec::with_capacity(count);
+        let mut sorted = vec![false; count];
+        while let Some(index) = (0..count).find(|&index| !sorted[index] && in_degrees[index] == 0) {
+            sorted[index] = true;
+            order.push(index);
+
+            for to in edges[index].iter() {
+                in_degrees[*to] -= 1;
+            }
+        }
+
+        if order.len() == count {
+            Ok(order)
+        } else {
+            Err(AppBuildError::CyclicOrdering(
+                (0..count)
+                    .filter(|&index| !sorted[index])
+                    .map(|index| String::from(stage_builders[index].name()))
+                    .collect(),
+            ))
+        }
     }
 
     fn has_stage(&self, stage_name: &str) -> bool {
@@ -217,25 +895,58 @@ impl AppBuilder {
 
 #[derive(Debug)]
 pub enum AppBuildError {
-    DuplicateName(AppStageBuilder),
+    DuplicateName(Box<AppStageBuilder>),
+    /// The ordering constraints form a cycle, contains the names of the stages in or after it.
+    CyclicOrdering(Vec<String>),
+    DuplicateStateName(Box<AppStateBuilder>),
+    /// The initial state is not added.
+    StateNotExist(String),
+    /// The stage of a state is not added.
+    StageNotExist(String),
+    StageInMultipleStates(String),
+    DuplicatePlugin(String),
+    /// The plugin depends on a plugin which is not added or disabled.
+    PluginNotExist {
+        plugin: String,
+        dependency: String,
+    },
+    /// The dependencies of the plugins form a cycle.
+    CyclicPlugins(Vec<String>),
 }
 
 pub struct AppStage {
     name: String,
+    // the stages created by `yam`, which can't be removed by `AppSettings`.
+    is_system: bool,
+    // the index in the stages sorted by `AppBuilder::build()`.
+    order: Option<usize>,
     time: RefCell<Time>,
+    fixed_time: RefCell<FixedTime>,
 
     startup: RefCell<Schedule>,
+    fixed_update: RefCell<Schedule>,
     process: RefCell<Schedule>,
     destroy: RefCell<Schedule>,
 }
 
 impl AppStage {
-    fn new(name: String, startup: Schedule, process: Schedule, destroy: Schedule) -> Self {
+    fn new(
+        name: String,
+        fixed_step: Duration,
+        startup: Schedule,
+        fixed_update: Schedule,
+        process: Schedule,
+        destroy: Schedule,
+    ) -> Self {
         Self {
             name,
+            is_system: false,
+            order: None,
             time: RefCell::new(Time::now()),
+            fixed_time: RefCell::new(FixedTime::new(fixed_step)),
 
             startup: RefCell::new(startup),
+            fixed_update: RefCell::new(fixed_update),
             process: RefCell::new(process),
             destroy: RefCell::new(destroy),
         }
@@ -245,10 +956,37 @@ impl AppStage {
         self.name.as_str()
     }
 
+    /// Whether the stage is created by `yam`, like `default_render`.
+    ///
+    /// The system stages always run after the stages of user, and can't be made rest.
+    pub fn is_system(&self) -> bool {
+        self.is_system
+    }
+
+    pub(crate) fn into_system(mut self) -> Self {
+        self.is_system = true;
+        self
+    }
+
+    /// The clock scale of the stage, see `Time::scale()`.
+    pub fn time_scale(&self) -> f32 {
+        self.time.borrow().scale()
+    }
+
+    /// Whether the clock of the stage is paused, see `Time::is_paused()`.
+    pub fn is_time_paused(&self) -> bool {
+        self.time.borrow().is_paused()
+    }
+
     pub(crate) fn init(&self, world: &mut World, resources: &mut Resources) {
-        *self.time.borrow_mut() = Time::now();
+        let time = Time::now_with_clock(&self.time.borrow());
+        *self.time.borrow_mut() = time;
         resources.insert::<Time>(*self.time.borrow_mut());
 
+        let step = self.fixed_time.borrow().step();
+        *self.fixed_time.borrow_mut() = FixedTime::new(step);
+        resources.insert::<FixedTime>(*self.fixed_time.borrow());
+
         self.startup.borrow_mut().execute(world, resources);
     }
 
@@ -256,12 +994,40 @@ impl AppStage {
         self.time.borrow_mut().tick();
         resources.insert::<Time>(*self.time.borrow_mut());
 
+        // NOTE: run the fixed schedule as many times as the accumulated time allows.
+        let mut fixed_time = self.fixed_time.borrow_mut();
+        fixed_time.accumulate(self.time.borrow().delta());
+        while fixed_time.expend() {
+            resources.insert::<FixedTime>(*fixed_time);
+
+            self.fixed_update.borrow_mut().execute(world, resources);
+        }
+        resources.insert::<FixedTime>(*fixed_time);
+
         self.process.borrow_mut().execute(world, resources);
     }
 
+    pub(crate) fn set_time_scale(&self, scale: f32) {
+        self.time.borrow_mut().set_scale(scale);
+    }
+
+    pub(crate) fn set_time_paused(&self, is_paused: bool) {
+        self.time.borrow_mut().set_paused(is_paused);
+    }
+
+    pub(crate) fn step_time(&self) {
+        self.time.borrow_mut().step();
+    }
+
+    /// Drop the time elapsed since the last frame, e.g. the stage rested.
+    pub(crate) fn skip_time(&self) {
+        self.time.borrow_mut().skip();
+    }
+
     pub(crate) fn free(&self, world: &mut World, resources: &mut Resources) {
         self.time.borrow_mut().tick();
         resources.insert::<Time>(*self.time.borrow_mut());
+        resources.insert::<FixedTime>(*self.fixed_time.borrow());
 
         self.destroy.borrow_mut().execute(world, resources);
     }
@@ -271,14 +1037,22 @@ impl fmt::Debug for AppStage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AppStage")
             .field("name", &self.name)
+            .field("is_system", &self.is_system)
             .finish()
     }
 }
 
 pub struct AppStageBuilder {
     name: String,
+    fixed_step: Duration,
+
+    // the ordering constraints, by the names of stages or groups.
+    before: Vec<String>,
+    after: Vec<String>,
+    groups: Vec<String>,
 
     builder_startup: Builder,
+    builder_fixed_update: Builder,
     builder_process: Builder,
     builder_destroy: Builder,
 
@@ -289,6 +1063,9 @@ impl fmt::Debug for AppStageBuilder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AppStageBuilder")
             .field("name", &self.name)
+            .field("before", &self.before)
+            .field("after", &self.after)
+            .field("groups", &self.groups)
             .finish()
     }
 }
@@ -297,8 +1074,14 @@ impl AppStageBuilder {
     pub fn new(name: String) -> Self {
         Self {
             name,
+            fixed_step: FixedTime::DEFAULT_STEP,
+
+            before: Default::default(),
+            after: Default::default(),
+            groups: Default::default(),
 
             builder_startup: Builder::default(),
+            builder_fixed_update: Builder::default(),
             builder_process: Builder::default(),
             builder_destroy: Builder::default(),
 
@@ -310,12 +1093,65 @@ impl AppStageBuilder {
         self.name.as_str()
     }
 
+    /// Set the interval between two executions of the fixed schedule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    pub fn set_fixed_step(mut self, step: Duration) -> Self {
+        assert!(step > Duration::default(), "ERR: The fixed step is zero.");
+        self.fixed_step = step;
+
+        self
+    }
+
+    /// Set how many times the fixed schedule executes per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not positive.
+    pub fn set_fixed_rate(self, rate: f64) -> Self {
+        assert!(rate > 0.0, "ERR: The fixed rate is not positive.");
+
+        self.set_fixed_step(Duration::from_secs_f64(1.0 / rate))
+    }
+
+    /// Run the stage before the stage or all stages of the group named `name`.
+    ///
+    /// NOTE: The constraints are resolved by `AppBuilder::build()`, the unknown names are
+    /// ignored, and the system stages always run after the stages of user.
+    pub fn runs_before(mut self, name: &str) -> Self {
+        self.before.push(String::from(name));
+
+        self
+    }
+
+    /// Run the stage after the stage or all stages of the group named `name`.
+    pub fn runs_after(mut self, name: &str) -> Self {
+        self.after.push(String::from(name));
+
+        self
+    }
+
+    /// Add the stage to the group, other stages can run before or after the whole group.
+    pub fn in_group(mut self, group: &str) -> Self {
+        self.groups.push(String::from(group));
+
+        self
+    }
+
     pub fn add_system_startup<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
         self.builder_startup.add_system(system);
 
         self
     }
 
+    pub fn add_system_fixed_update<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
+        self.builder_fixed_update.add_system(system);
+
+        self
+    }
+
     pub fn add_system_process<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
         self.builder_process.add_system(system);
 
@@ -334,6 +1170,15 @@ impl AppStageBuilder {
         self
     }
 
+    pub fn add_thread_local_system_fixed_update<T: Runnable + 'static>(
+        mut self,
+        system: T,
+    ) -> Self {
+        self.builder_fixed_update.add_thread_local(system);
+
+        self
+    }
+
     pub fn add_thread_local_system_process<T: Runnable + 'static>(mut self, system: T) -> Self {
         self.builder_process.add_thread_local(system);
 
@@ -355,6 +1200,15 @@ impl AppStageBuilder {
         self
     }
 
+    pub fn add_thread_local_fn_fixed_update<F: FnMut(&mut World, &mut Resources) + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.builder_fixed_update.add_thread_local_fn(f);
+
+        self
+    }
+
     pub fn add_thread_local_fn_process<F: FnMut(&mut World, &mut Resources) + 'static>(
         mut self,
         f: F,
@@ -376,7 +1230,9 @@ impl AppStageBuilder {
     pub fn build(mut self) -> AppStage {
         AppStage::new(
             self.name,
+            self.fixed_step,
             self.builder_startup.build(),
+            self.builder_fixed_update.build(),
             self.builder_process.build(),
             self.builder_destroy.build(),
         )
@@ -393,11 +1249,71 @@ impl AppStageBuilder {
     }
 }
 
+/// Decide when the next frame runs, by the frame limiter and the low power mode.
+#[derive(Debug)]
+struct FramePacer {
+    // the earliest time to run the next frame.
+    next_frame: Instant,
+    // something happened since the last frame, e.g. the input events come.
+    requested: bool,
+}
+
+impl FramePacer {
+    fn new() -> Self {
+        Self {
+            next_frame: Instant::now(),
+            requested: true,
+        }
+    }
+
+    fn request_frame(&mut self) {
+        self.requested = true;
+    }
+
+    fn is_ready(&self, now: Instant, low_power: bool) -> bool {
+        (self.requested || !low_power) && now >= self.next_frame
+    }
+
+    /// Start a frame at `now`, and schedule the next one.
+    fn advance(&mut self, now: Instant, target_fps: Option<u32>) {
+        self.requested = false;
+        self.next_frame = match target_fps {
+            Some(fps) => {
+                let period = Duration::from_secs(1) / fps;
+                let next_frame = self.next_frame + period;
+
+                // NOTE: The frame is late(or the app was idle), don't try to catch up.
+                if next_frame < now {
+                    now + period
+                } else {
+                    next_frame
+                }
+            }
+            None => now,
+        };
+    }
+
+    fn control_flow(&self, now: Instant, low_power: bool) -> ControlFlow {
+        if low_power && !self.requested {
+            ControlFlow::Wait
+        } else if now < self.next_frame {
+            ControlFlow::WaitUntil(self.next_frame)
+        } else {
+            ControlFlow::Poll
+        }
+    }
+}
+
 pub struct AppSettings {
     busy_stages: Rc<RefCell<Vec<AppStage>>>,
 
     spare_stages: Vec<AppStage>,
     commands: Vec<AppCommand>,
+    stage_evts: Vec<StageEvent>,
+
+    target_fps: Option<u32>,
+    low_power: bool,
+    redraw_requested: bool,
 }
 
 impl AppSettings {
@@ -407,46 +1323,75 @@ impl AppSettings {
 
             spare_stages: Default::default(),
             commands: Default::default(),
+            stage_evts: Default::default(),
+
+            target_fps: None,
+            low_power: false,
+            redraw_requested: false,
         }
     }
 
     /// apply settings for app and return a flag indicating whether user request to quit
-    fn apply(&mut self) -> bool {
-        fn fuck_borrow_checker(busy_stages: &Vec<AppStage>, stage_name: &str) -> usize {
-            busy_stages
-                .iter()
-                .enumerate()
-                .find(|(_, stage)| stage.name() == stage_name)
-                .map(|(index, _)| index)
-                .unwrap()
-        }
-
-        for cmd in self.commands.drain(..) {
+    ///
+    /// The stages may be changed between queueing and applying a command, e.g. two commands
+    /// push the stages with same name, the failed commands are pushed to `failures`.
+    fn apply(&mut self, failures: &mut Vec<AppCommandFailed>) -> bool {
+        for cmd in std::mem::take(&mut self.commands) {
             match cmd {
                 AppCommand::PushStageToWorkBefore {
                     stage,
                     after_stage_name,
                 } => {
-                    let index =
-                        fuck_borrow_checker(&self.busy_stages.borrow(), after_stage_name.as_str());
-                    self.busy_stages.borrow_mut().insert(index, stage);
+                    let index = Self::position(&self.busy_stages.borrow(), &after_stage_name);
+
+                    self.push_stage_at(index, stage, after_stage_name, failures);
                 }
                 AppCommand::PushStageToWork { stage } => {
-                    self.busy_stages.borrow_mut().push(stage);
+                    // NOTE: Keep the system stages at the end.
+                    let index = {
+                        let busy_stages = self.busy_stages.borrow();
+
+                        busy_stages
+                            .iter()
+                            .position(|stage| stage.is_system())
+                            .unwrap_or_else(|| busy_stages.len())
+                    };
+
+                    self.push_stage_at(Some(index), stage, String::new(), failures);
                 }
                 AppCommand::PushStageToWorkAfter {
                     stage,
                     before_stage_name,
                 } => {
-                    let index =
-                        fuck_borrow_checker(&self.busy_stages.borrow(), before_stage_name.as_str());
-                    self.busy_stages.borrow_mut().insert(index + 1, stage);
+                    let index = Self::position(&self.busy_stages.borrow(), &before_stage_name)
+                        .map(|index| index + 1);
+
+                    self.push_stage_at(index, stage, before_stage_name, failures);
                 }
                 AppCommand::MakeBusyStageToRest { stage_name } => {
-                    let index =
-                        fuck_borrow_checker(&self.busy_stages.borrow(), stage_name.as_str());
-                    let stage = self.busy_stages.borrow_mut().remove(index);
-                    self.spare_stages.push(stage);
+                    let index = Self::position(&self.busy_stages.borrow(), &stage_name);
+
+                    if let Some(index) = index {
+                        let stage = self.busy_stages.borrow_mut().remove(index);
+                        self.stage_evts.push(StageEvent::Rested(stage_name));
+                        self.spare_stages.push(stage);
+                    } else {
+                        failures.push(AppCommandFailed::StageNotExistInBusy(stage_name));
+                    }
+                }
+                AppCommand::SetTimeScale { stage_name, scale } => {
+                    self.with_stage(stage_name, failures, |stage| stage.set_time_scale(scale));
+                }
+                AppCommand::SetTimePaused {
+                    stage_name,
+                    is_paused,
+                } => {
+                    self.with_stage(stage_name, failures, |stage| {
+                        stage.set_time_paused(is_paused)
+                    });
+                }
+                AppCommand::StepTime { stage_name } => {
+                    self.with_stage(stage_name, failures, |stage| stage.step_time());
                 }
                 AppCommand::AppQuit => {
                     return true;
@@ -457,22 +1402,16 @@ impl AppSettings {
         false
     }
 
-    pub fn busy_stage<'a>(&'a self, stage_name: &str) -> Option<&'a AppStage> {
-        let stages: &'a Vec<AppStage> = unsafe {
-            // TODO: write safety words
-            &self.busy_stages.try_borrow_unguarded().unwrap()
-        };
-
-        stages.iter().find(|stage| stage.name() == stage_name)
+    pub fn busy_stage(&self, stage_name: &str) -> Option<Ref<'_, AppStage>> {
+        Ref::filter_map(self.busy_stages.borrow(), |stages| {
+            stages.iter().find(|stage| stage.name() == stage_name)
+        })
+        .ok()
     }
 
-    pub fn busy_stage_iter<'a>(&'a self) -> Iter<'a, AppStage> {
-        let stages: &'a Vec<AppStage> = unsafe {
-            // TODO: write safety words
-            &self.busy_stages.try_borrow_unguarded().unwrap()
-        };
-
-        stages.iter()
+    /// Return the busy stages in the order they run.
+    pub fn busy_stages(&self) -> Ref<'_, [AppStage]> {
+        Ref::map(self.busy_stages.borrow(), |stages| stages.as_slice())
     }
 
     pub fn spare_stage(&self, stage_name: &str) -> Option<&AppStage> {
@@ -517,6 +1456,11 @@ impl AppSettings {
             .is_some()
     }
 
+    /// Return `true` if the stage is a system stage like `default_render`.
+    pub fn is_system_stage(&self, stage_name: &str) -> bool {
+        matches!(self.busy_stage(stage_name), Some(stage) if stage.is_system())
+    }
+
     pub fn is_in_spare(&self, stage_name: &str) -> bool {
         self.spare_stages
             .iter()
@@ -539,12 +1483,15 @@ impl AppSettings {
         }
     }
 
+    /// Push the stage to work before the busy stage, the system stages can't be the anchor.
     pub fn push_stage_to_work_before<'a>(
         &mut self,
         stage: AppStage,
         after_stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if self.is_in_busy(after_stage_name) {
+        if self.is_system_stage(after_stage_name) {
+            Err(AppSettingsError::SystemStage(after_stage_name))
+        } else if self.is_in_busy(after_stage_name) {
             if self.is_in_busy(stage.name()) {
                 Err(AppSettingsError::DuplicateNameInBusy(stage))
             } else if self.is_in_spare(stage.name()) {
@@ -565,6 +1512,7 @@ impl AppSettings {
         }
     }
 
+    /// Push the stage to work, it runs before the system stages.
     pub fn push_stage_to_work<'a>(&mut self, stage: AppStage) -> Result<(), AppSettingsError<'a>> {
         if self.is_in_busy(stage.name()) {
             Err(AppSettingsError::DuplicateNameInBusy(stage))
@@ -577,12 +1525,16 @@ impl AppSettings {
         }
     }
 
+    /// Push the stage to work after the busy stage, the system stages can't be the anchor because
+    /// they always run after the stages of user.
     pub fn push_stage_to_work_after<'a>(
         &mut self,
         stage: AppStage,
         before_stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if self.is_in_busy(before_stage_name) {
+        if self.is_system_stage(before_stage_name) {
+            Err(AppSettingsError::SystemStage(before_stage_name))
+        } else if self.is_in_busy(before_stage_name) {
             if self.is_in_busy(stage.name()) {
                 Err(AppSettingsError::DuplicateNameInBusy(stage))
             } else if self.is_in_spare(stage.name()) {
@@ -608,7 +1560,9 @@ impl AppSettings {
         stage_name: &'a str,
         after_stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if let Some(stage) = self.take_spare_stage(stage_name) {
+        if self.is_system_stage(after_stage_name) {
+            Err(AppSettingsError::SystemStage(after_stage_name))
+        } else if let Some(stage) = self.take_spare_stage(stage_name) {
             self.push_stage_to_work_before(stage, after_stage_name)
         } else {
             Err(AppSettingsError::StageNotExistInSpare(stage_name, None))
@@ -631,7 +1585,9 @@ impl AppSettings {
         stage_name: &'a str,
         before_stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if let Some(stage) = self.take_spare_stage(stage_name) {
+        if self.is_system_stage(before_stage_name) {
+            Err(AppSettingsError::SystemStage(before_stage_name))
+        } else if let Some(stage) = self.take_spare_stage(stage_name) {
             self.push_stage_to_work_after(stage, before_stage_name)
         } else {
             Err(AppSettingsError::StageNotExistInSpare(stage_name, None))
@@ -650,11 +1606,14 @@ impl AppSettings {
         }
     }
 
+    /// Make the busy stage rest, the system stages like `default_render` can't rest.
     pub fn make_busy_stage_rest<'a>(
         &mut self,
         stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if self.is_in_busy(stage_name) {
+        if self.is_system_stage(stage_name) {
+            Err(AppSettingsError::SystemStage(stage_name))
+        } else if self.is_in_busy(stage_name) {
             self.commands.push(AppCommand::MakeBusyStageToRest {
                 stage_name: String::from(stage_name),
             });
@@ -665,9 +1624,203 @@ impl AppSettings {
         }
     }
 
+    /// Scale the clock of the stage, e.g. `0.5` for slow-motion.
+    ///
+    /// The scale must be in `[0.0, MAX_TIME_SCALE]`. The stage can be busy or spare, the new
+    /// scale takes effect at the next frame.
+    pub fn set_time_scale<'a>(
+        &mut self,
+        stage_name: &'a str,
+        scale: f32,
+    ) -> Result<(), AppSettingsError<'a>> {
+        if !(0.0..=MAX_TIME_SCALE).contains(&scale) {
+            Err(AppSettingsError::InvalidTimeScale(scale))
+        } else if self.is_in_busy(stage_name) || self.is_in_spare(stage_name) {
+            self.commands.push(AppCommand::SetTimeScale {
+                stage_name: String::from(stage_name),
+                scale,
+            });
+
+            Ok(())
+        } else {
+            Err(AppSettingsError::StageNotExist(stage_name))
+        }
+    }
+
+    /// Pause the clock of the stage, `Time::delta()` of the stage will be zero.
+    ///
+    /// The stage keeps working, only its clock stops.
+    pub fn pause_time<'a>(&mut self, stage_name: &'a str) -> Result<(), AppSettingsError<'a>> {
+        self.push_time_paused(stage_name, true)
+    }
+
+    /// Resume the paused clock of the stage.
+    pub fn resume_time<'a>(&mut self, stage_name: &'a str) -> Result<(), AppSettingsError<'a>> {
+        self.push_time_paused(stage_name, false)
+    }
+
+    /// Advance the paused clock of the stage for exactly one frame, used for debugging.
+    pub fn step_time<'a>(&mut self, stage_name: &'a str) -> Result<(), AppSettingsError<'a>> {
+        if self.is_in_busy(stage_name) || self.is_in_spare(stage_name) {
+            self.commands.push(AppCommand::StepTime {
+                stage_name: String::from(stage_name),
+            });
+
+            Ok(())
+        } else {
+            Err(AppSettingsError::StageNotExist(stage_name))
+        }
+    }
+
     pub fn quit(&mut self) {
         self.commands.push(AppCommand::AppQuit);
     }
+
+    /// Limit the frame rate on CPU side, `None` for unlimited.
+    ///
+    /// It works with any `PresentMode`, e.g. `Mailbox` with 60 fps saves the power without the
+    /// latency of `Fifo`.
+    pub fn set_target_fps<'a>(&mut self, fps: Option<u32>) -> Result<(), AppSettingsError<'a>> {
+        if fps == Some(0) {
+            Err(AppSettingsError::InvalidTargetFps(0))
+        } else {
+            self.target_fps = fps;
+
+            Ok(())
+        }
+    }
+
+    pub fn target_fps(&self) -> Option<u32> {
+        self.target_fps
+    }
+
+    /// In low power mode, the app sleeps until the input events come or `Self::request_redraw()`
+    /// is called, useful for tools and editors.
+    ///
+    /// NOTE: `Time::delta()` of the first frame after sleeping is the whole sleeping time.
+    pub fn set_low_power(&mut self, low_power: bool) {
+        self.low_power = low_power;
+    }
+
+    pub fn is_low_power(&self) -> bool {
+        self.low_power
+    }
+
+    /// Run the next frame even if there are no input events in low power mode, e.g. to keep an
+    /// animation playing.
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    /// Make the busy stage rest immediately, without `destroy`.
+    pub(crate) fn rest_stage_now(&mut self, stage_name: &str) {
+        let mut busy_stages = self.busy_stages.borrow_mut();
+
+        if let Some(index) = busy_stages
+            .iter()
+            .position(|stage| stage.name() == stage_name)
+        {
+            self.spare_stages.push(busy_stages.remove(index));
+            self.stage_evts
+                .push(StageEvent::Rested(String::from(stage_name)));
+        }
+    }
+
+    /// Make the spare stage work immediately, without `startup`.
+    ///
+    /// NOTE: The stage is put back to the place sorted by `AppBuilder::build()`.
+    pub(crate) fn work_stage_now(&mut self, stage_name: &str) {
+        if let Some(stage) = self.take_spare_stage(stage_name) {
+            let mut busy_stages = self.busy_stages.borrow_mut();
+            let index = busy_stages
+                .iter()
+                .position(|other| match (other.order, stage.order) {
+                    _ if other.is_system() => true,
+                    (Some(a), Some(b)) => a > b,
+                    _ => false,
+                })
+                .unwrap_or_else(|| busy_stages.len());
+
+            busy_stages.insert(index, stage);
+            self.stage_evts
+                .push(StageEvent::Worked(String::from(stage_name)));
+        }
+    }
+
+    /// Take the transitions of stages since the last call, they're sent by `Events<StageEvent>`.
+    fn take_stage_events(&mut self) -> Vec<StageEvent> {
+        std::mem::take(&mut self.stage_evts)
+    }
+
+    fn take_redraw_request(&mut self) -> bool {
+        std::mem::take(&mut self.redraw_requested)
+    }
+
+    fn has_commands(&self) -> bool {
+        !self.commands.is_empty()
+    }
+
+    fn position(stages: &[AppStage], stage_name: &str) -> Option<usize> {
+        stages.iter().position(|stage| stage.name() == stage_name)
+    }
+
+    // Insert the stage to work at `index`, or make it rest if the anchor stage has gone.
+    fn push_stage_at(
+        &mut self,
+        index: Option<usize>,
+        stage: AppStage,
+        anchor: String,
+        failures: &mut Vec<AppCommandFailed>,
+    ) {
+        let stage_name = String::from(stage.name());
+
+        if self.is_in_busy(&stage_name) || self.is_in_spare(&stage_name) {
+            failures.push(AppCommandFailed::DuplicateName(stage_name));
+        } else if let Some(index) = index {
+            self.busy_stages.borrow_mut().insert(index, stage);
+            self.stage_evts.push(StageEvent::Worked(stage_name));
+        } else {
+            self.spare_stages.push(stage);
+            failures.push(AppCommandFailed::AnchorNotExist { stage_name, anchor });
+        }
+    }
+
+    // Run `f` on the busy or spare stage named `stage_name` if it exists.
+    fn with_stage<F: FnOnce(&AppStage)>(
+        &self,
+        stage_name: String,
+        failures: &mut Vec<AppCommandFailed>,
+        f: F,
+    ) {
+        let busy_stages = self.busy_stages.borrow();
+
+        if let Some(stage) = busy_stages
+            .iter()
+            .chain(self.spare_stages.iter())
+            .find(|stage| stage.name() == stage_name)
+        {
+            f(stage);
+        } else {
+            failures.push(AppCommandFailed::StageNotExist(stage_name));
+        }
+    }
+
+    fn push_time_paused<'a>(
+        &mut self,
+        stage_name: &'a str,
+        is_paused: bool,
+    ) -> Result<(), AppSettingsError<'a>> {
+        if self.is_in_busy(stage_name) || self.is_in_spare(stage_name) {
+            self.commands.push(AppCommand::SetTimePaused {
+                stage_name: String::from(stage_name),
+                is_paused,
+            });
+
+            Ok(())
+        } else {
+            Err(AppSettingsError::StageNotExist(stage_name))
+        }
+    }
 }
 
 impl fmt::Debug for AppSettings {
@@ -676,6 +1829,8 @@ impl fmt::Debug for AppSettings {
             .field("busy_stages", &RefCell::borrow(&self.busy_stages))
             .field("spare_stages", &self.spare_stages)
             .field("commands", &self.commands)
+            .field("target_fps", &self.target_fps)
+            .field("low_power", &self.low_power)
             .finish()
     }
 }
@@ -696,9 +1851,46 @@ enum AppCommand {
     MakeBusyStageToRest {
         stage_name: String,
     },
+    SetTimeScale {
+        stage_name: String,
+        scale: f32,
+    },
+    SetTimePaused {
+        stage_name: String,
+        is_paused: bool,
+    },
+    StepTime {
+        stage_name: String,
+    },
     AppQuit,
 }
 
+/// The transitions of stages between busy and spare, published as `Events<StageEvent>`.
+///
+/// They're caused by the commands of `AppSettings` or the transitions of `AppState`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageEvent {
+    /// The stage is pushed to work.
+    Worked(String),
+    /// The busy stage is made to rest.
+    Rested(String),
+}
+
+/// The event sent when a command queued by `AppSettings` fails at the start of next frame, e.g.
+/// the stage it refers has been taken by `AppSettings::take_spare_stage()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppCommandFailed {
+    /// Another stage with the same name has been pushed, the stage is dropped.
+    DuplicateName(String),
+    /// The stage to push the stage before or after has gone, the stage is pushed to rest.
+    AnchorNotExist {
+        stage_name: String,
+        anchor: String,
+    },
+    StageNotExistInBusy(String),
+    StageNotExist(String),
+}
+
 #[derive(Debug)]
 pub enum AppSettingsError<'a> {
     DuplicateNameInBusy(AppStage),
@@ -706,4 +1898,559 @@ pub enum AppSettingsError<'a> {
     StageNotExist(&'a str),
     StageNotExistInBusy(&'a str, Option<AppStage>),
     StageNotExistInSpare(&'a str, Option<AppStage>),
+    InvalidTimeScale(f32),
+    InvalidTargetFps(u32),
+    /// The system stage can't be removed, or be the anchor to push the stage before or after.
+    SystemStage(&'a str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::time::MAX_FIXED_STEPS_PER_FRAME,
+        events::EventReader,
+        input::{InputRecording, KeyCode},
+    };
+
+    #[derive(Default)]
+    struct Counter {
+        startup: u32,
+        fixed_update: u32,
+        process: u32,
+    }
+
+    #[test]
+    fn test_run_headless_frames() {
+        AppBuilder::new()
+            .create_stage_builder(String::from("frames"))
+            .unwrap()
+            .add_thread_local_fn_startup(|_, resources| {
+                resources.get_mut_or_default::<Counter>().startup += 1;
+            })
+            .add_thread_local_fn_process(|_, resources| {
+                resources.get_mut_or_default::<Counter>().process += 1;
+            })
+            .add_thread_local_fn_destroy(|_, resources| {
+                let counter = resources.get::<Counter>().unwrap();
+
+                assert_eq!(counter.startup, 1);
+                assert_eq!(counter.process, 8);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(8));
+    }
+
+    #[test]
+    fn test_run_headless_until_quit() {
+        AppBuilder::new()
+            .create_stage_builder(String::from("quit"))
+            .unwrap()
+            .add_thread_local_fn_process(|_, resources| {
+                assert!(resources.get::<Input>().is_some());
+                assert!(resources.get::<Time>().is_some());
+
+                let process = {
+                    let mut counter = resources.get_mut_or_default::<Counter>();
+                    counter.process += 1;
+                    counter.process
+                };
+
+                if process == 8 {
+                    resources.get_mut::<AppSettings>().unwrap().quit();
+                }
+            })
+            .add_thread_local_fn_destroy(|_, resources| {
+                assert_eq!(resources.get::<Counter>().unwrap().process, 8);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::UntilQuit);
+    }
+
+    #[test]
+    fn test_run_headless_playback() {
+        let recording = InputRecording::from_frames(vec![
+            vec![],
+            vec![InputEvent::Key {
+                keycode: KeyCode::Space,
+                pressed: true,
+            }],
+        ]);
+
+        AppBuilder::new()
+            .create_stage_builder(String::from("playback"))
+            .unwrap()
+            .add_thread_local_fn_startup(move |_, resources| {
+                resources
+                    .get_mut::<InputRecorder>()
+                    .unwrap()
+                    .play(recording.clone());
+            })
+            .add_thread_local_fn_process(|_, resources| {
+                let process = {
+                    let mut counter = resources.get_mut_or_default::<Counter>();
+                    counter.process += 1;
+                    counter.process
+                };
+
+                let input = resources.get::<Input>().unwrap();
+                assert_eq!(input.keyboard.just_pressed(KeyCode::Space), process == 2);
+                assert_eq!(input.keyboard.pressed(KeyCode::Space), process > 2);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(4));
+    }
+
+    #[test]
+    fn test_time_control() {
+        AppBuilder::new()
+            .create_stage_builder(String::from("clock"))
+            .unwrap()
+            .add_thread_local_fn_process(|_, resources| {
+                let frame = {
+                    let mut counter = resources.get_mut_or_default::<Counter>();
+                    counter.process += 1;
+                    counter.process
+                };
+
+                let time = *resources.get::<Time>().unwrap();
+                let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+                match frame {
+                    1 => {
+                        settings.set_time_scale("clock", 0.5).unwrap();
+                        settings.pause_time("clock").unwrap();
+                        assert!(settings.set_time_scale("clock", -1.0).is_err());
+                        assert!(matches!(
+                            settings.set_time_scale("clock", 1e30),
+                            Err(AppSettingsError::InvalidTimeScale(_))
+                        ));
+                        assert!(settings.set_time_scale("clock", f32::NAN).is_err());
+                        assert!(settings.pause_time("missing").is_err());
+                    }
+                    2 => {
+                        assert_eq!(time.scale(), 0.5);
+                        assert_eq!(time.delta(), Duration::default());
+
+                        settings.step_time("clock").unwrap();
+                    }
+                    3 => {
+                        assert_eq!(time.delta(), time.unscaled_delta().mul_f32(0.5));
+
+                        settings.resume_time("clock").unwrap();
+                    }
+                    _ => assert!(!time.is_paused()),
+                }
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(4));
+    }
+
+    #[test]
+    fn test_fixed_update() {
+        let step = Duration::from_micros(10);
+
+        AppBuilder::new()
+            .create_stage_builder(String::from("fixed"))
+            .unwrap()
+            .set_fixed_step(step)
+            .add_thread_local_fn_fixed_update(|_, resources| {
+                resources.get_mut_or_default::<Counter>().fixed_update += 1;
+            })
+            .add_thread_local_fn_process(move |_, resources| {
+                let (frame, fixed_updates) = {
+                    let mut counter = resources.get_mut_or_default::<Counter>();
+                    counter.process += 1;
+                    (counter.process, std::mem::take(&mut counter.fixed_update))
+                };
+
+                let fixed_time = *resources.get::<FixedTime>().unwrap();
+                assert_eq!(fixed_time.total(), step * fixed_time.step_count() as u32);
+
+                let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+                match frame {
+                    // NOTE: The delta of the first frame may be shorter than the cap.
+                    1 => {}
+                    // NOTE: The frame takes 100 steps, the fixed schedule runs at most
+                    // `MAX_FIXED_STEPS_PER_FRAME` times.
+                    2 => {
+                        assert_eq!(fixed_updates, MAX_FIXED_STEPS_PER_FRAME);
+
+                        settings.pause_time("fixed").unwrap();
+                    }
+                    // NOTE: No time is accumulated while the clock is paused.
+                    3 => {
+                        assert_eq!(fixed_updates, 0);
+                        assert_eq!(fixed_time.alpha(), 0.0);
+
+                        settings.resume_time("fixed").unwrap();
+                    }
+                    _ => assert_eq!(fixed_updates, MAX_FIXED_STEPS_PER_FRAME),
+                }
+
+                thread::sleep(step * 100);
+            })
+            .add_thread_local_fn_destroy(|_, resources| {
+                assert_eq!(resources.get::<Counter>().unwrap().process, 4);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(4));
+    }
+
+    fn stage_names(app: &App) -> Vec<&str> {
+        app.busy_stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    #[test]
+    fn test_stage_ordering() {
+        let app = AppBuilder::new()
+            .add_stage_builder(AppStageBuilder::new(String::from("render_ui")).runs_after("ai"))
+            .unwrap()
+            .add_stage_builder(
+                AppStageBuilder::new(String::from("physics")).runs_before("gameplay"),
+            )
+            .unwrap()
+            .add_stage_builder(AppStageBuilder::new(String::from("ai")).in_group("gameplay"))
+            .unwrap()
+            .add_stage_builder(AppStageBuilder::new(String::from("audio")))
+            .unwrap()
+            .add_stage_builder(
+                AppStageBuilder::new(String::from("player"))
+                    .in_group("gameplay")
+                    .runs_after("missing"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            stage_names(&app),
+            ["physics", "ai", "render_ui", "audio", "player"]
+        );
+
+        let err = AppBuilder::new()
+            .add_stage_builder(AppStageBuilder::new(String::from("a")).runs_before("b"))
+            .unwrap()
+            .add_stage_builder(AppStageBuilder::new(String::from("b")).in_group("g"))
+            .unwrap()
+            .add_stage_builder(AppStageBuilder::new(String::from("c")).runs_after("a"))
+            .unwrap()
+            .add_stage_builder(AppStageBuilder::new(String::from("d")).runs_before("a"))
+            .unwrap()
+            .add_stage_builder(
+                AppStageBuilder::new(String::from("e"))
+                    .runs_after("g")
+                    .runs_before("a"),
+            )
+            .unwrap()
+            .build()
+            .unwrap_err();
+
+        match err {
+            AppBuildError::CyclicOrdering(names) => assert_eq!(names, ["a", "b", "c", "e"]),
+            _ => panic!("ERR: The cycle is not detected."),
+        }
+    }
+
+    #[test]
+    fn test_system_stage() {
+        AppBuilder::new()
+            .create_stage_builder(String::from("user"))
+            .unwrap()
+            .add_thread_local_fn_process(|_, resources| {
+                let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+                if settings.is_in_spare("extra") {
+                    assert!(matches!(
+                        settings.make_spare_stage_work_after("extra", "default_transform"),
+                        Err(AppSettingsError::SystemStage("default_transform"))
+                    ));
+                    assert!(settings.is_in_spare("extra"));
+
+                    settings.make_spare_stage_work("extra").unwrap();
+                } else if settings.is_in_busy("extra") {
+                    assert_eq!(
+                        busy_names(&settings),
+                        ["user", "extra", "default_transform"]
+                    );
+
+                    settings.quit();
+                } else {
+                    assert!(settings
+                        .busy_stage("default_transform")
+                        .unwrap()
+                        .is_system());
+                    assert!(matches!(
+                        settings.make_busy_stage_rest("default_transform"),
+                        Err(AppSettingsError::SystemStage("default_transform"))
+                    ));
+                    assert!(matches!(
+                        settings.push_stage_to_work_after(stage("after"), "default_transform"),
+                        Err(AppSettingsError::SystemStage("default_transform"))
+                    ));
+                    assert!(matches!(
+                        settings.push_stage_to_work_before(stage("before"), "default_transform"),
+                        Err(AppSettingsError::SystemStage("default_transform"))
+                    ));
+
+                    settings
+                        .push_stage_to_rest(AppStageBuilder::new(String::from("extra")).build())
+                        .unwrap();
+                }
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(8));
+    }
+
+    fn stage(name: &str) -> AppStage {
+        AppStageBuilder::new(String::from(name)).build()
+    }
+
+    fn busy_names(settings: &AppSettings) -> Vec<String> {
+        settings
+            .busy_stages()
+            .iter()
+            .map(|stage| String::from(stage.name()))
+            .collect()
+    }
+
+    #[test]
+    fn test_app_commands() {
+        let busy_stages = Rc::new(RefCell::new(vec![
+            stage("a"),
+            stage("system").into_system(),
+        ]));
+        let mut settings = AppSettings::new(&busy_stages);
+        let mut failures = Vec::new();
+
+        // PushStageToWork, PushStageToWorkBefore, PushStageToWorkAfter
+        settings.push_stage_to_work(stage("b")).unwrap();
+        settings.push_stage_to_work_before(stage("c"), "a").unwrap();
+        settings.push_stage_to_work_after(stage("d"), "a").unwrap();
+        assert!(!settings.apply(&mut failures));
+        assert_eq!(busy_names(&settings), ["c", "a", "d", "b", "system"]);
+
+        // MakeBusyStageToRest, SetTimeScale, SetTimePaused, StepTime
+        settings.make_busy_stage_rest("c").unwrap();
+        settings.set_time_scale("c", 0.5).unwrap();
+        settings.pause_time("a").unwrap();
+        settings.step_time("a").unwrap();
+        assert!(!settings.apply(&mut failures));
+        assert_eq!(busy_names(&settings), ["a", "d", "b", "system"]);
+        assert_eq!(settings.spare_stage("c").unwrap().time_scale(), 0.5);
+        assert!(settings.busy_stage("a").unwrap().is_time_paused());
+
+        // AppQuit
+        settings.resume_time("a").unwrap();
+        settings.quit();
+        assert!(settings.apply(&mut failures));
+        assert!(!settings.busy_stage("a").unwrap().is_time_paused());
+        assert!(!settings.has_commands());
+
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn test_app_commands_failed() {
+        let busy_stages = Rc::new(RefCell::new(vec![
+            stage("a"),
+            stage("system").into_system(),
+        ]));
+        let mut settings = AppSettings::new(&busy_stages);
+        let mut failures = Vec::new();
+
+        settings.push_stage_to_rest(stage("spare")).unwrap();
+
+        // the stages with same name are pushed in one frame.
+        settings.push_stage_to_work(stage("b")).unwrap();
+        settings.push_stage_to_work(stage("b")).unwrap();
+        // the anchor rests before the stages are pushed.
+        settings.make_busy_stage_rest("a").unwrap();
+        settings.make_busy_stage_rest("a").unwrap();
+        settings.push_stage_to_work_before(stage("c"), "a").unwrap();
+        settings.push_stage_to_work_after(stage("d"), "a").unwrap();
+        // the stage is taken before the commands are applied.
+        settings.set_time_scale("spare", 2.0).unwrap();
+        settings.pause_time("spare").unwrap();
+        settings.step_time("spare").unwrap();
+        settings.take_spare_stage("spare").unwrap();
+
+        assert!(!settings.apply(&mut failures));
+        assert_eq!(
+            failures,
+            [
+                AppCommandFailed::DuplicateName(String::from("b")),
+                AppCommandFailed::StageNotExistInBusy(String::from("a")),
+                AppCommandFailed::AnchorNotExist {
+                    stage_name: String::from("c"),
+                    anchor: String::from("a"),
+                },
+                AppCommandFailed::AnchorNotExist {
+                    stage_name: String::from("d"),
+                    anchor: String::from("a"),
+                },
+                AppCommandFailed::StageNotExist(String::from("spare")),
+                AppCommandFailed::StageNotExist(String::from("spare")),
+                AppCommandFailed::StageNotExist(String::from("spare")),
+            ]
+        );
+        assert_eq!(busy_names(&settings), ["b", "system"]);
+        assert!(["a", "c", "d"]
+            .iter()
+            .all(|stage_name| settings.is_in_spare(stage_name)));
+    }
+
+    #[test]
+    fn test_app_command_failed_event() {
+        let mut reader = EventReader::<AppCommandFailed>::default();
+
+        AppBuilder::new()
+            .create_stage_builder(String::from("user"))
+            .unwrap()
+            .add_thread_local_fn_process(move |_, resources| {
+                let failures: Vec<AppCommandFailed> = {
+                    let events = resources.get::<Events<AppCommandFailed>>().unwrap();
+                    reader.iter(&events).cloned().collect()
+                };
+
+                if failures.is_empty() {
+                    let mut settings = resources.get_mut::<AppSettings>().unwrap();
+                    settings.push_stage_to_work(stage("extra")).unwrap();
+                    settings.push_stage_to_work(stage("extra")).unwrap();
+                } else {
+                    assert_eq!(
+                        failures,
+                        [AppCommandFailed::DuplicateName(String::from("extra"))]
+                    );
+
+                    resources.get_mut_or_default::<Counter>().process += 1;
+                    resources.get_mut::<AppSettings>().unwrap().quit();
+                }
+            })
+            .add_thread_local_fn_destroy(|_, resources| {
+                assert_eq!(resources.get::<Counter>().unwrap().process, 1);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(4));
+    }
+
+    #[test]
+    fn test_stage_event() {
+        let mut reader = EventReader::<StageEvent>::default();
+
+        AppBuilder::new()
+            .create_stage_builder(String::from("user"))
+            .unwrap()
+            .add_thread_local_fn_process(move |_, resources| {
+                let stage_evts: Vec<StageEvent> = {
+                    let events = resources.get::<Events<StageEvent>>().unwrap();
+                    reader.iter(&events).cloned().collect()
+                };
+
+                let frame = {
+                    let mut counter = resources.get_mut_or_default::<Counter>();
+                    counter.process += 1;
+                    counter.process - 1
+                };
+
+                let mut settings = resources.get_mut::<AppSettings>().unwrap();
+                match frame {
+                    0 => {
+                        assert!(stage_evts.is_empty());
+                        settings.push_stage_to_work(stage("extra")).unwrap();
+                    }
+                    1 => {
+                        assert_eq!(stage_evts, [StageEvent::Worked(String::from("extra"))]);
+                        settings.make_busy_stage_rest("extra").unwrap();
+                    }
+                    2 => {
+                        assert_eq!(stage_evts, [StageEvent::Rested(String::from("extra"))]);
+                        settings.quit();
+                    }
+                    _ => unreachable!(),
+                }
+            })
+            .add_thread_local_fn_destroy(|_, resources| {
+                assert_eq!(resources.get::<Counter>().unwrap().process, 3);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(5));
+    }
+
+    #[test]
+    fn test_frame_pacer() {
+        let mut pacer = FramePacer::new();
+        let start = Instant::now();
+        let period = Duration::from_secs(1) / 50;
+
+        // unlimited
+        assert!(pacer.is_ready(start, false));
+        pacer.advance(start, None);
+        assert_eq!(pacer.control_flow(start, false), ControlFlow::Poll);
+
+        // limited
+        pacer.advance(start, Some(50));
+        assert!(!pacer.is_ready(start, false));
+        assert_eq!(
+            pacer.control_flow(start, false),
+            ControlFlow::WaitUntil(start + period)
+        );
+        assert!(pacer.is_ready(start + period, false));
+
+        // late frames don't catch up
+        let late = start + period * 10;
+        pacer.advance(late, Some(50));
+        assert_eq!(
+            pacer.control_flow(late, false),
+            ControlFlow::WaitUntil(late + period)
+        );
+
+        // low power
+        let now = late + period;
+        assert!(!pacer.is_ready(now, true));
+        assert_eq!(pacer.control_flow(now, true), ControlFlow::Wait);
+
+        pacer.request_frame();
+        assert!(pacer.is_ready(now, true));
+        assert_eq!(pacer.control_flow(now, true), ControlFlow::Poll);
+    }
+
+    #[test]
+    fn test_run_headless_target_fps() {
+        let start = Instant::now();
+
+        AppBuilder::new()
+            .create_stage_builder(String::from("limited"))
+            .unwrap()
+            .add_thread_local_fn_startup(|_, resources| {
+                let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+                assert!(settings.set_target_fps(Some(0)).is_err());
+                settings.set_target_fps(Some(100)).unwrap();
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(5));
+
+        // NOTE: The first frame runs immediately.
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
 }
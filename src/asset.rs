@@ -0,0 +1,362 @@
+use crate::{
+    app::{AppBuildError, AppBuilder},
+    misc::color::Rgba,
+    plugin::Plugin,
+};
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+/// The default directory `AssetServer` loads assets from.
+pub const DEFAULT_ASSET_ROOT: &str = "assets";
+
+/// An image stored as tightly packed RGBA8 rows from top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Texture {
+    /// Create a `Texture` from RGBA8 datas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `data` is not `width * height * 4`, or the size is zero.
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "ERR: The size of texture cannot be zero."
+        );
+        assert_eq!(
+            data.len(),
+            (width * height * 4) as usize,
+            "ERR: The length of data does not match the size."
+        );
+
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Create a `width x height` texture filled with `color`.
+    pub fn from_color(width: u32, height: u32, color: Rgba) -> Self {
+        let data = [color.r, color.g, color.b, color.a]
+            .iter()
+            .copied()
+            .cycle()
+            .take((width * height * 4) as usize)
+            .collect();
+
+        Self::new(width, height, data)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// A typed reference to an asset stored in the `AssetServer`.
+///
+/// It's cheap to copy, the asset is only stored once.
+pub struct Handle<T> {
+    id: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(id: u64) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+// NOTE: Implement these traits by hand, `derive` would require `T` to implement them.
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({})", std::any::type_name::<T>(), self.id)
+    }
+}
+
+/// The storage of assets of type `T`, accessed by `Handle<T>`.
+///
+/// NOTE: The assets are only reachable through the `AssetServer`.
+pub(crate) struct Assets<T> {
+    next_id: u64,
+    assets: HashMap<u64, T>,
+}
+
+impl<T> Assets<T> {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            assets: HashMap::new(),
+        }
+    }
+
+    /// Store the asset and return the handle of it.
+    pub fn add(&mut self, asset: T) -> Handle<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.assets.insert(id, asset);
+
+        Handle::new(id)
+    }
+
+    pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        self.assets.get(&handle.id)
+    }
+
+    pub fn remove(&mut self, handle: &Handle<T>) -> Option<T> {
+        self.assets.remove(&handle.id)
+    }
+
+    pub fn contains(&self, handle: &Handle<T>) -> bool {
+        self.assets.contains_key(&handle.id)
+    }
+}
+
+impl<T> Default for Assets<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The errors may occur when loading an asset.
+#[derive(Debug)]
+pub enum AssetError {
+    /// The file cannot be read.
+    Io { path: PathBuf, error: io::Error },
+    /// The file cannot be decoded as an image.
+    Decode {
+        path: PathBuf,
+        error: image::ImageError,
+    },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io { path, error } => {
+                write!(f, "failed to read '{}': {}", path.display(), error)
+            }
+            AssetError::Decode { path, error } => {
+                write!(f, "failed to decode '{}': {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::Io { error, .. } => Some(error),
+            AssetError::Decode { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Load assets from files under a root directory(`assets/` by default).
+///
+/// A file is only loaded once, loading the same path again returns the same handle.
+///
+/// `yam` inserts an `AssetServer` into `Resources` before running, and the renderers
+/// look up the textures of sprites in it.
+pub struct AssetServer {
+    root: PathBuf,
+    textures: Assets<Texture>,
+    texture_paths: HashMap<PathBuf, Handle<Texture>>,
+}
+
+impl AssetServer {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            textures: Assets::new(),
+            texture_paths: HashMap::new(),
+        }
+    }
+
+    /// The directory which the paths of assets are relative to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Load an image file(png, bmp, tga, ico) as `Texture`, `path` is relative to the root directory.
+    pub fn load_texture<P: AsRef<Path>>(&mut self, path: P) -> Result<Handle<Texture>, AssetError> {
+        let path = self.root.join(path);
+
+        // NOTE: Canonicalize the path, so different spellings of the same file are deduplicated.
+        let key = path.canonicalize().map_err(|error| AssetError::Io {
+            path: path.clone(),
+            error,
+        })?;
+
+        if let Some(handle) = self.texture_paths.get(&key) {
+            if self.textures.contains(handle) {
+                return Ok(*handle);
+            }
+        }
+
+        let image = image::io::Reader::open(&key)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|error| AssetError::Io {
+                path: path.clone(),
+                error,
+            })?
+            .decode()
+            .map_err(|error| AssetError::Decode { path, error })?
+            .into_rgba8();
+
+        let (width, height) = image.dimensions();
+        let handle = self
+            .textures
+            .add(Texture::new(width, height, image.into_raw()));
+
+        self.texture_paths.insert(key, handle);
+
+        Ok(handle)
+    }
+
+    /// Store a texture created in code.
+    pub fn add_texture(&mut self, texture: Texture) -> Handle<Texture> {
+        self.textures.add(texture)
+    }
+
+    pub fn texture(&self, handle: &Handle<Texture>) -> Option<&Texture> {
+        self.textures.get(handle)
+    }
+
+    /// Remove the texture, the sprites using it are not drawn anymore.
+    pub fn remove_texture(&mut self, handle: &Handle<Texture>) -> Option<Texture> {
+        self.textures.remove(handle)
+    }
+
+    pub(crate) fn textures(&self) -> &Assets<Texture> {
+        &self.textures
+    }
+}
+
+impl Default for AssetServer {
+    fn default() -> Self {
+        Self::new(DEFAULT_ASSET_ROOT)
+    }
+}
+
+/// The default plugin of `AssetServer`.
+pub struct AssetPlugin;
+
+impl Plugin for AssetPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_resource(AssetServer::default());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets() {
+        let mut textures: Assets<Texture> = Assets::new();
+
+        let red = textures.add(Texture::from_color(2, 2, Rgba::RED));
+        let blue = textures.add(Texture::from_color(1, 1, Rgba::BLUE));
+
+        assert_ne!(red, blue);
+        assert_eq!(textures.assets.len(), 2);
+        assert_eq!(textures.get(&red).unwrap().size(), (2, 2));
+        assert_eq!(textures.get(&blue).unwrap().data(), &[0, 0, 255, 255]);
+
+        textures.remove(&red);
+
+        assert!(!textures.contains(&red));
+        assert!(textures.contains(&blue));
+    }
+
+    #[test]
+    fn test_asset_server_load_texture() {
+        let mut asset_server = AssetServer::default();
+
+        let yam = asset_server.load_texture("images/sprites/yam.png").unwrap();
+        let same = asset_server
+            .load_texture("images/../images/sprites/yam.png")
+            .unwrap();
+
+        assert_eq!(yam, same);
+        assert_eq!(asset_server.textures().assets.len(), 1);
+        assert_eq!(asset_server.texture(&yam).unwrap().size(), (32, 32));
+
+        match asset_server.load_texture("images/sprites/not_exist.png") {
+            Err(AssetError::Io { .. }) => {}
+            other => panic!("ERR: Expect io error, but get {:?}", other),
+        }
+
+        match asset_server.load_texture("shaders/sprite/sprite.vert") {
+            Err(AssetError::Decode { .. }) => {}
+            other => panic!("ERR: Expect decode error, but get {:?}", other),
+        }
+
+        // NOTE: The removed texture is loaded again.
+        assert!(asset_server.remove_texture(&yam).is_some());
+        assert!(asset_server.texture(&yam).is_none());
+
+        let reloaded = asset_server.load_texture("images/sprites/yam.png").unwrap();
+        assert_ne!(reloaded, yam);
+        assert!(asset_server.texture(&reloaded).is_some());
+    }
+}
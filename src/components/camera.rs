@@ -1,5 +1,6 @@
 use crate::{
     nalgebra::{Matrix4, Orthographic3},
+    window::WindowId,
     DEFAULT_HEIGHT, DEFAULT_WIDTH,
 };
 
@@ -41,3 +42,8 @@ impl Default for Camera2D {
         Self::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
     }
 }
+
+/// Make the `Camera2D` on the same entity draw into an extra window of `Windows`, instead of the
+/// primary window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowTarget(pub WindowId);
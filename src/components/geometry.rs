@@ -220,11 +220,17 @@ impl Geometry {
     }
 }
 
+unsafe impl bytemuck::Zeroable for Geometry {}
+unsafe impl bytemuck::Pod for Geometry {}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BorderThickness {
     /// Thickness in `local space`.
     LocalSpace(f32),
     /// Thickness in `screen space`.
+    ///
+    /// It's in physical pixels by default, or in logical pixels if `Window::set_hidpi_scaling()`
+    /// is enabled.
     ScreenSpace(f32),
 }
 
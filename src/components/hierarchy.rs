@@ -0,0 +1,553 @@
+use super::transform::Transform2D;
+
+use crate::{
+    app::{AppBuildError, AppBuilder, AppStage, AppStageBuilder},
+    legion::{component, maybe_changed, Entity, EntityStore, IntoQuery, World},
+    plugin::Plugin,
+};
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::{Deref, DerefMut},
+};
+
+/// The parent of an entity in the transform hierarchy.
+///
+/// An entity with `Parent` and `LocalTransform2D` gets its `Transform2D`(world space)
+/// calculated from the `Transform2D` of its parent every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+/// The children of an entity in the transform hierarchy.
+///
+/// It's maintained by `yam` according to `Parent`, DO NOT modify it by yourself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn iter(&self) -> std::slice::Iter<'_, Entity> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Position, rotation and scale of an entity relative to its `Parent`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalTransform2D(pub Transform2D);
+
+impl Deref for LocalTransform2D {
+    type Target = Transform2D;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for LocalTransform2D {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Create the `AppStage` which propagates `Transform2D` along the transform hierarchy.
+///
+/// It works before `default_render`, so the renderers always read the up-to-date `Transform2D`.
+pub(crate) fn create_app_stage_transform() -> AppStage {
+    let mut propagate = transform_propagation();
+
+    AppStageBuilder::new(String::from("default_transform"))
+        .add_thread_local_fn_process(move |world, _| {
+            propagate(world);
+        })
+        .build()
+}
+
+/// Create the function which propagates `Transform2D` along the transform hierarchy, it returns
+/// the number of entities walked.
+///
+/// NOTE: Only the entities in the hierarchy are walked, the moving entities without `Parent` and
+/// `Children` cost nothing, so the million-entity worlds stay cheap.
+fn transform_propagation() -> impl FnMut(&mut World) -> usize {
+    let mut q_linked = <(Entity, &Parent)>::query().filter(maybe_changed::<Parent>());
+    let mut q_moved_roots = <(Entity, &Transform2D, &Children)>::query()
+        .filter(!component::<Parent>() & maybe_changed::<Transform2D>());
+    // NOTE: An entity losing its `Parent` moves to another archetype, so it's passed here.
+    let mut q_detached = <(Entity, &LocalTransform2D)>::query()
+        .filter(!component::<Parent>() & maybe_changed::<LocalTransform2D>());
+    let mut q_moved_locals = <(Entity, &Parent, &LocalTransform2D)>::query()
+        .filter(maybe_changed::<LocalTransform2D>() | maybe_changed::<Parent>());
+
+    // NOTE: The queries are kept between frames, so `maybe_changed` only passes the chunks
+    // modified since last frame. `maybe_changed::<T>()` only works if `T` is fetched by the
+    // query, or it passes all chunks.
+    //
+    // (child, parent) linked by `Children`, used to find the old parent of a moved child.
+    let mut links: HashMap<Entity, Entity> = HashMap::new();
+
+    move |world: &mut World| {
+        let linked: Vec<(Entity, Entity)> = q_linked
+            .iter(world)
+            .map(|(child, parent)| (*child, parent.0))
+            .collect();
+
+        // NOTE: The old parent is dirty, so it removes the child from its `Children`.
+        let mut dirty: Vec<Entity> = Vec::new();
+
+        for (child, parent) in linked {
+            if link_child(world, parent, child) {
+                dirty.extend(links.insert(child, parent).filter(|old| *old != parent));
+            }
+        }
+
+        dirty.extend(q_moved_roots.iter(world).map(|(root, _, _)| *root));
+
+        let detached: Vec<Entity> = q_detached
+            .iter(world)
+            .map(|(child, _)| *child)
+            .filter(|child| links.contains_key(child))
+            .collect();
+        for child in detached {
+            dirty.extend(links.remove(&child));
+            dirty.push(child);
+        }
+
+        dirty.extend(q_moved_locals.iter(world).map(|(child, _, _)| *child));
+
+        propagate_transform2d(world, &mut links, dirty)
+    }
+}
+
+/// The default plugin of the system stage `default_transform`.
+pub struct TransformPlugin;
+
+impl Plugin for TransformPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_system_stage(|_| Some(create_app_stage_transform()));
+
+        Ok(())
+    }
+}
+
+/// Add `child` to the `Children` of `parent`, return `false` if it's not linked.
+///
+/// NOTE: An entity can't be the parent of itself.
+fn link_child(world: &mut World, parent: Entity, child: Entity) -> bool {
+    if parent == child {
+        return false;
+    }
+
+    if let Some(mut entry) = world.entry(parent) {
+        if let Ok(children) = entry.get_component_mut::<Children>() {
+            if !children.0.contains(&child) {
+                children.0.push(child);
+            }
+        } else {
+            entry.add_component(Children(vec![child]));
+        }
+
+        true
+    } else {
+        false
+    }
+}
+
+/// Return the depth of the entity in the hierarchy, `None` if its ancestors form a cycle.
+fn depth(world: &World, entity: Entity) -> Option<usize> {
+    let mut ancestors = HashSet::new();
+    let mut current = entity;
+
+    while let Some(parent) = world
+        .entry_ref(current)
+        .ok()
+        .and_then(|entry| entry.get_component::<Parent>().ok().copied())
+    {
+        if !ancestors.insert(current) {
+            return None;
+        }
+        current = parent.0;
+    }
+
+    Some(ancestors.len())
+}
+
+/// Update the `Transform2D` of dirty entities and their descendants.
+///
+/// The shallower entities are updated first, so each entity reads the final `Transform2D` of its
+/// parent and is updated only once. The entities in a `Parent` cycle are skipped. Return the
+/// number of entities walked.
+fn propagate_transform2d(
+    world: &mut World,
+    links: &mut HashMap<Entity, Entity>,
+    dirty: Vec<Entity>,
+) -> usize {
+    let mut dirty: Vec<(usize, Entity)> = dirty
+        .into_iter()
+        .filter_map(|entity| depth(world, entity).map(|depth| (depth, entity)))
+        .collect();
+    dirty.sort_by_key(|(depth, _)| *depth);
+
+    let mut visited: HashSet<Entity> = HashSet::new();
+    let mut stack: Vec<Entity> = Vec::new();
+
+    for (_, entity) in dirty {
+        stack.push(entity);
+
+        while let Some(entity) = stack.pop() {
+            if !visited.insert(entity) {
+                continue;
+            }
+
+            if update_transform2d(world, entity).is_some() {
+                stack.extend(alive_children(world, links, entity));
+            }
+        }
+    }
+
+    visited.len()
+}
+
+/// Calculate the `Transform2D` of the entity from its parent, return `None` if it's not in the
+/// hierarchy.
+fn update_transform2d(world: &mut World, entity: Entity) -> Option<Transform2D> {
+    let parent = world
+        .entry_ref(entity)
+        .ok()?
+        .get_component::<Parent>()
+        .ok()
+        .copied();
+
+    match parent {
+        Some(parent) => {
+            let parent_t = *world
+                .entry_ref(parent.0)
+                .ok()?
+                .get_component::<Transform2D>()
+                .ok()?;
+
+            let mut entry = world.entry_mut(entity).ok()?;
+            let world_t = parent_t.compose(entry.get_component::<LocalTransform2D>().ok()?);
+            // NOTE: Skip the child without `Transform2D`, it's not renderable.
+            *entry.get_component_mut::<Transform2D>().ok()? = world_t;
+
+            Some(world_t)
+        }
+        None => world
+            .entry_ref(entity)
+            .ok()?
+            .get_component::<Transform2D>()
+            .ok()
+            .copied(),
+    }
+}
+
+/// Return the children of the entity, and remove the ones which are destroyed or have changed
+/// their parent.
+fn alive_children(
+    world: &mut World,
+    links: &mut HashMap<Entity, Entity>,
+    entity: Entity,
+) -> Vec<Entity> {
+    let children = match world
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Children>().ok().cloned())
+    {
+        Some(children) => children.0,
+        None => return Vec::new(),
+    };
+
+    let (alive, dead): (Vec<Entity>, Vec<Entity>) = children.into_iter().partition(|child| {
+        world
+            .entry_ref(*child)
+            .ok()
+            .and_then(|entry| entry.get_component::<Parent>().ok().copied())
+            == Some(Parent(entity))
+    });
+
+    for child in dead {
+        if links.get(&child) == Some(&entity) {
+            links.remove(&child);
+        }
+    }
+
+    if let Ok(mut entry) = world.entry_mut(entity) {
+        if let Ok(children) = entry.get_component_mut::<Children>() {
+            if children.0.len() != alive.len() {
+                children.0 = alive.clone();
+            }
+        }
+    }
+
+    alive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{legion::Resources, nalgebra::Vector2};
+
+    struct Marker;
+
+    fn position(world: &World, entity: Entity) -> Vector2<f32> {
+        world
+            .entry_ref(entity)
+            .unwrap()
+            .get_component::<Transform2D>()
+            .unwrap()
+            .position
+    }
+
+    fn set_local(world: &mut World, entity: Entity, x: f32, y: f32) {
+        world
+            .entry(entity)
+            .unwrap()
+            .get_component_mut::<LocalTransform2D>()
+            .unwrap()
+            .position = Vector2::new(x, y);
+    }
+
+    #[test]
+    fn test_propagate_transform2d() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let stage = create_app_stage_transform();
+
+        let tank = world.push((Transform2D::with_position(10.0, 0.0),));
+        let turret = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(0.0, 2.0)),
+            Parent(tank),
+        ));
+        let barrel = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(1.0, 0.0)),
+            Parent(turret),
+        ));
+
+        stage.init(&mut world, &mut resources);
+        stage.play(&mut world, &mut resources);
+
+        assert_eq!(position(&world, turret), Vector2::new(10.0, 2.0));
+        assert_eq!(position(&world, barrel), Vector2::new(11.0, 2.0));
+
+        // Move the root.
+        world
+            .entry(tank)
+            .unwrap()
+            .get_component_mut::<Transform2D>()
+            .unwrap()
+            .position
+            .x = 20.0;
+        stage.play(&mut world, &mut resources);
+
+        assert_eq!(position(&world, barrel), Vector2::new(21.0, 2.0));
+
+        // Detach the barrel from the turret.
+        world.entry(barrel).unwrap().remove_component::<Parent>();
+        stage.play(&mut world, &mut resources);
+
+        let turret_children = world
+            .entry_ref(turret)
+            .unwrap()
+            .get_component::<Children>()
+            .unwrap()
+            .len();
+        assert_eq!(turret_children, 0);
+    }
+
+    #[test]
+    fn test_propagate_stale_nested_parent() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let stage = create_app_stage_transform();
+
+        let tank = world.push((Transform2D::with_position(10.0, 0.0),));
+        // NOTE: Create the archetype of the barrel first, so the barrel is iterated before the
+        // turret.
+        let dummy = world.push((
+            Transform2D::default(),
+            LocalTransform2D::default(),
+            Parent(tank),
+            Marker,
+        ));
+        world.remove(dummy);
+        let turret = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(0.0, 2.0)),
+            Parent(tank),
+        ));
+        let barrel = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(1.0, 0.0)),
+            Parent(turret),
+            Marker,
+        ));
+
+        stage.init(&mut world, &mut resources);
+        stage.play(&mut world, &mut resources);
+        assert_eq!(position(&world, barrel), Vector2::new(11.0, 2.0));
+
+        // Move the turret and the barrel in the same frame.
+        set_local(&mut world, turret, 0.0, 5.0);
+        set_local(&mut world, barrel, 2.0, 0.0);
+        stage.play(&mut world, &mut resources);
+
+        assert_eq!(position(&world, turret), Vector2::new(10.0, 5.0));
+        assert_eq!(position(&world, barrel), Vector2::new(12.0, 5.0));
+    }
+
+    #[test]
+    fn test_propagate_parent_cycle() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let stage = create_app_stage_transform();
+
+        let tank = world.push((Transform2D::with_position(10.0, 0.0),));
+        let turret = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(0.0, 2.0)),
+            Parent(tank),
+        ));
+        let selfish = world.push((Transform2D::default(), LocalTransform2D::default()));
+        world.entry(selfish).unwrap().add_component(Parent(selfish));
+        let a = world.push((Transform2D::default(), LocalTransform2D::default()));
+        let b = world.push((
+            Transform2D::default(),
+            LocalTransform2D::default(),
+            Parent(a),
+        ));
+        world.entry(a).unwrap().add_component(Parent(b));
+
+        // NOTE: It must not loop forever.
+        stage.init(&mut world, &mut resources);
+        stage.play(&mut world, &mut resources);
+
+        assert!(world
+            .entry_ref(selfish)
+            .unwrap()
+            .get_component::<Children>()
+            .is_err());
+        assert_eq!(position(&world, turret), Vector2::new(10.0, 2.0));
+
+        set_local(&mut world, a, 1.0, 1.0);
+        stage.play(&mut world, &mut resources);
+        assert_eq!(position(&world, turret), Vector2::new(10.0, 2.0));
+    }
+
+    #[test]
+    fn test_reparent_across_archetypes() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let stage = create_app_stage_transform();
+
+        let tank = world.push((Transform2D::with_position(10.0, 0.0),));
+        let truck = world.push((Transform2D::with_position(-10.0, 0.0), Marker));
+        let turret = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(0.0, 2.0)),
+            Parent(tank),
+            Marker,
+        ));
+        let barrel = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(1.0, 0.0)),
+            Parent(turret),
+        ));
+
+        stage.init(&mut world, &mut resources);
+        stage.play(&mut world, &mut resources);
+        assert_eq!(position(&world, barrel), Vector2::new(11.0, 2.0));
+
+        // Move the barrel from the turret to the truck.
+        *world
+            .entry(barrel)
+            .unwrap()
+            .get_component_mut::<Parent>()
+            .unwrap() = Parent(truck);
+        stage.play(&mut world, &mut resources);
+
+        assert_eq!(position(&world, barrel), Vector2::new(-9.0, 0.0));
+        assert!(world
+            .entry_ref(truck)
+            .unwrap()
+            .get_component::<Children>()
+            .unwrap()
+            .iter()
+            .any(|child| *child == barrel));
+
+        // The turret drops the barrel when it moves, and the barrel follows the truck only.
+        set_local(&mut world, turret, 0.0, 3.0);
+        world
+            .entry(truck)
+            .unwrap()
+            .get_component_mut::<Transform2D>()
+            .unwrap()
+            .position
+            .y = 1.0;
+        stage.play(&mut world, &mut resources);
+
+        assert_eq!(position(&world, barrel), Vector2::new(-9.0, 1.0));
+        assert!(world
+            .entry_ref(turret)
+            .unwrap()
+            .get_component::<Children>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_propagate_skips_flat_entities() {
+        let mut world = World::default();
+        let mut propagate = transform_propagation();
+
+        for i in 0..1000 {
+            world.push((Transform2D::with_position(i as f32, 0.0),));
+        }
+        let tank = world.push((Transform2D::with_position(10.0, 0.0), Marker));
+        let turret = world.push((
+            Transform2D::default(),
+            LocalTransform2D(Transform2D::with_position(0.0, 2.0)),
+            Parent(tank),
+        ));
+
+        assert_eq!(propagate(&mut world), 2);
+        assert_eq!(propagate(&mut world), 0);
+
+        // NOTE: Moving the flat entities doesn't walk them.
+        for transform in <&mut Transform2D>::query()
+            .filter(!component::<Parent>() & !component::<Children>())
+            .iter_mut(&mut world)
+        {
+            transform.position.y += 1.0;
+        }
+        assert_eq!(propagate(&mut world), 0);
+
+        world
+            .entry(tank)
+            .unwrap()
+            .get_component_mut::<Transform2D>()
+            .unwrap()
+            .position
+            .x = 20.0;
+        assert_eq!(propagate(&mut world), 2);
+        assert_eq!(position(&world, turret), Vector2::new(20.0, 2.0));
+
+        // NOTE: The detached child is walked once, and its old parent drops it.
+        world.entry(turret).unwrap().remove_component::<Parent>();
+        assert_eq!(propagate(&mut world), 2);
+        assert!(world
+            .entry_ref(tank)
+            .unwrap()
+            .get_component::<Children>()
+            .unwrap()
+            .is_empty());
+        assert_eq!(propagate(&mut world), 0);
+    }
+}
@@ -1,5 +1,6 @@
 pub mod camera;
 pub mod geometry;
+pub mod hierarchy;
 pub mod sprite;
 pub mod time;
 pub mod transform;
@@ -1,5 +1,74 @@
-use crate::misc::color::Rgba;
+use crate::{
+    asset::{Handle, Texture},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
 
+/// A textured quad, its size and placement come from `Transform2D`.
+#[derive(Debug, Clone, Copy)]
 pub struct Sprite {
+    /// The texture to draw.
+    pub texture: Handle<Texture>,
+    /// The area of the texture to draw.
+    pub uv_rect: UvRect,
+    /// Multiplied with the color of texture.
     pub color: Rgba,
+    /// The origin of the sprite in `local space`,
+    /// `(0.0, 0.0)` is the left-bottom corner, `(1.0, 1.0)` is the right-top corner.
+    pub pivot: Vector2<f32>,
+}
+
+impl Sprite {
+    /// Create a sprite which draws the whole texture, centered at its position.
+    pub fn new(texture: Handle<Texture>) -> Self {
+        Self {
+            texture,
+            uv_rect: UvRect::default(),
+            color: Rgba::WHITE,
+            pivot: Vector2::new(0.5, 0.5),
+        }
+    }
+
+    pub fn with_color(texture: Handle<Texture>, color: Rgba) -> Self {
+        Self {
+            color,
+            ..Self::new(texture)
+        }
+    }
+}
+
+/// A rectangle area of texture in uv coordinates.
+///
+/// `(0.0, 0.0)` is the left-top corner of the texture, `(1.0, 1.0)` is the right-bottom corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvRect {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl UvRect {
+    pub fn new(min_u: f32, min_v: f32, max_u: f32, max_v: f32) -> Self {
+        Self {
+            min: Vector2::new(min_u, min_v),
+            max: Vector2::new(max_u, max_v),
+        }
+    }
+
+    /// Create the uv rect of a pixel area(origin at the left-top corner) of the texture.
+    pub fn from_pixels(texture: &Texture, x: u32, y: u32, width: u32, height: u32) -> Self {
+        let (tw, th) = (texture.width() as f32, texture.height() as f32);
+
+        Self::new(
+            x as f32 / tw,
+            y as f32 / th,
+            (x + width) as f32 / tw,
+            (y + height) as f32 / th,
+        )
+    }
+}
+
+impl Default for UvRect {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
 }
@@ -3,14 +3,22 @@
 use colored::Colorize;
 
 use std::{
+    convert::TryFrom,
     fmt,
     time::{Duration, Instant},
 };
 
+/// The maximum factor of `Time::scale()`, a larger one may overflow the scaled `Duration`.
+pub const MAX_TIME_SCALE: f32 = 1000.0;
+
 /// The untility to get time information.
 ///
 /// You CANNOT constrcutor or modify `Time`, it just only provide the time information about main-loop of engine to you.
 ///
+/// Each `AppStage` owns its clock, which can be scaled, paused or stepped frame by frame through `AppSettings`.
+/// `Self::delta()` and `Self::total()` report the scaled time, `Self::unscaled_delta()` and `Self::unscaled_total()`
+/// report the wall-clock time.
+///
 /// If you want a time utility to diagnose the time cost of a piece of code, then `ProfileTimer` may meets your needs.
 #[derive(Clone, Copy)]
 pub struct Time {
@@ -18,6 +26,14 @@ pub struct Time {
 
     // Σ[(fps - fps_avg)^2]
     fps_diff_pow: u64,
+
+    scale: f32,
+    is_paused: bool,
+    // Whether to advance one frame in paused state.
+    is_stepping: bool,
+
+    scaled_delta: Duration,
+    scaled_total: Duration,
 }
 
 impl Time {
@@ -27,6 +43,22 @@ impl Time {
             pt: DiagnosticTimer::now(),
 
             fps_diff_pow: Default::default(),
+
+            scale: 1.0,
+            is_paused: false,
+            is_stepping: false,
+
+            scaled_delta: Default::default(),
+            scaled_total: Default::default(),
+        }
+    }
+
+    /// Create instance of `Time` which inherits the clock settings(scale, pause) from `other`.
+    pub(crate) fn now_with_clock(other: &Self) -> Self {
+        Self {
+            scale: other.scale,
+            is_paused: other.is_paused,
+            ..Self::now()
         }
     }
 
@@ -40,6 +72,14 @@ impl Time {
         self.pt.stop_record();
 
         self.fps_diff_pow += f32::powi(self.fps() - self.fps_avg(), 2) as u64;
+
+        self.scaled_delta = if !self.is_paused || self.is_stepping {
+            self.pt.delta().mul_f32(self.scale)
+        } else {
+            Duration::default()
+        };
+        self.scaled_total += self.scaled_delta;
+        self.is_stepping = false;
     }
 
     /// Whether `Self::begin_record()` has been called.
@@ -55,11 +95,65 @@ impl Time {
         self.start_record();
     }
 
-    /// The interval between `Self::begin_record()` and `Self::finish_record()`.
+    /// Restart the current time record, the time elapsed since the last tick isn't counted.
+    pub(crate) fn skip(&mut self) {
+        self.pt.begin_tick = Instant::now();
+    }
+
+    /// Set the factor multiplied to the wall-clock delta.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is not in `[0.0, MAX_TIME_SCALE]`.
+    pub(crate) fn set_scale(&mut self, scale: f32) {
+        assert!(
+            (0.0..=MAX_TIME_SCALE).contains(&scale),
+            "ERR: The time scale must be in [0.0, MAX_TIME_SCALE]."
+        );
+
+        self.scale = scale;
+    }
+
+    /// Pause or resume the clock, `Self::delta()` is zero in paused state.
+    pub(crate) fn set_paused(&mut self, is_paused: bool) {
+        self.is_paused = is_paused;
+    }
+
+    /// Advance the paused clock for exactly one frame.
+    pub(crate) fn step(&mut self) {
+        self.is_stepping = true;
+    }
+
+    /// The factor multiplied to the wall-clock delta.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Whether the clock is paused.
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// The scaled interval between two frames, it's zero if the clock is paused.
     pub fn delta(&self) -> Duration {
+        self.scaled_delta
+    }
+
+    /// The sum of scaled deltas.
+    pub fn total(&self) -> Duration {
+        self.scaled_total
+    }
+
+    /// The interval between `Self::begin_record()` and `Self::finish_record()`.
+    pub fn unscaled_delta(&self) -> Duration {
         self.pt.delta()
     }
 
+    /// The duration from construction to last record.
+    pub fn unscaled_total(&self) -> Duration {
+        self.delta_avg() * self.record_count() as u32
+    }
+
     /// The average of deltas recorded.
     pub fn delta_avg(&self) -> Duration {
         self.pt.delta_avg()
@@ -75,12 +169,12 @@ impl Time {
         self.pt.record_count()
     }
 
-    /// The calculated value of framerate: 1.0 / `Self::delta()`.
+    /// The calculated value of framerate: 1.0 / `Self::unscaled_delta()`.
     pub fn fps(&self) -> f32 {
-        let is_zero = self.delta().as_micros() == 0;
+        let is_zero = self.unscaled_delta().as_micros() == 0;
 
         if !is_zero {
-            1.0 / self.delta().as_secs_f32()
+            1.0 / self.unscaled_delta().as_secs_f32()
         } else {
             f32::NAN
         }
@@ -102,11 +196,6 @@ impl Time {
         f32::sqrt(self.fps_variance())
     }
 
-    /// The duration from construction to last record.
-    pub fn total(&self) -> Duration {
-        self.delta_avg() * self.record_count() as u32
-    }
-
     /// The variance of framerates recorded.
     fn fps_variance(&self) -> f32 {
         let tick_count = std::cmp::max(1, self.record_count());
@@ -153,6 +242,104 @@ impl fmt::Display for Time {
     }
 }
 
+/// The maximum number of fixed steps accumulated in one frame.
+///
+/// Prevents the fixed schedule from running endlessly when a frame takes much longer than a step.
+pub(crate) const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// The longest `Duration` the fixed time saturates at.
+const SATURATED: Duration = Duration::from_secs(u64::MAX);
+
+/// The time information about the fixed schedule of `AppStage`.
+///
+/// The fixed schedule runs zero or more times per frame with the same `Self::step()`, so the
+/// simulation driven by it does not depend on the framerate.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTime {
+    step: Duration,
+    accumulator: Duration,
+    step_count: u64,
+}
+
+impl FixedTime {
+    /// The default step of the fixed schedule, 60 times per second.
+    pub const DEFAULT_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    /// Create instance of `FixedTime`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    pub(crate) fn new(step: Duration) -> Self {
+        assert!(step > Duration::default(), "ERR: The fixed step is zero.");
+
+        Self {
+            step,
+            accumulator: Default::default(),
+            step_count: Default::default(),
+        }
+    }
+
+    /// Accumulate the delta of a frame.
+    pub(crate) fn accumulate(&mut self, delta: Duration) {
+        // NOTE: Saturate, the step may be any non-zero `Duration`.
+        self.accumulator = std::cmp::min(
+            self.accumulator.checked_add(delta).unwrap_or(SATURATED),
+            self.step
+                .checked_mul(MAX_FIXED_STEPS_PER_FRAME)
+                .unwrap_or(SATURATED),
+        );
+    }
+
+    /// Consume a step from the accumulated time, return `false` if there's not enough time.
+    pub(crate) fn expend(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            self.step_count += 1;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The interval between two fixed steps.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// The count of fixed steps executed.
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    /// The duration simulated by fixed steps, saturated at `u64::MAX` seconds.
+    pub fn total(&self) -> Duration {
+        // NOTE: `Duration * u32` truncates the count and panics on overflow.
+        let nanos = self.step.as_nanos() * self.step_count as u128;
+
+        match u64::try_from(nanos / NANOS_PER_SEC) {
+            Ok(secs) => Duration::new(secs, (nanos % NANOS_PER_SEC) as u32),
+            Err(_) => SATURATED,
+        }
+    }
+
+    /// The progress(in [0, 1)) between the last fixed step and the next one.
+    ///
+    /// Used to interpolate the states simulated by fixed steps when rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_STEP)
+    }
+}
+
 /// A timer used to diagnose the performance of a piece of code.
 #[derive(Clone, Copy)]
 pub struct DiagnosticTimer {
@@ -347,6 +534,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_time_clock() {
+        let mut time = Time::now();
+
+        time.set_scale(0.5);
+        std::thread::sleep(Duration::from_millis(2));
+        time.tick();
+        assert_eq!(time.delta(), time.unscaled_delta().mul_f32(0.5));
+
+        time.set_paused(true);
+        time.tick();
+        assert_eq!(time.delta(), Duration::default());
+
+        time.step();
+        std::thread::sleep(Duration::from_millis(2));
+        time.tick();
+        assert_eq!(time.delta(), time.unscaled_delta().mul_f32(0.5));
+
+        time.tick();
+        assert_eq!(time.delta(), Duration::default());
+
+        let inherited = Time::now_with_clock(&time);
+        assert_eq!(inherited.scale(), 0.5);
+        assert!(inherited.is_paused());
+
+        let mut time = Time::now();
+        time.set_scale(MAX_TIME_SCALE);
+        std::thread::sleep(Duration::from_millis(2));
+        time.tick();
+        assert_eq!(time.delta(), time.unscaled_delta().mul_f32(MAX_TIME_SCALE));
+    }
+
+    #[test]
+    fn test_fixed_time() {
+        let mut fixed = FixedTime::new(Duration::from_millis(10));
+
+        fixed.accumulate(Duration::from_millis(25));
+        assert!(fixed.expend());
+        assert!(fixed.expend());
+        assert!(!fixed.expend());
+        assert_eq!(fixed.step_count(), 2);
+        assert!((fixed.alpha() - 0.5).abs() < 1e-4);
+
+        // The accumulated time is clamped to prevent endless steps.
+        fixed.accumulate(Duration::from_secs(1));
+        let mut steps = 0;
+        while fixed.expend() {
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_FIXED_STEPS_PER_FRAME);
+    }
+
+    #[test]
+    fn test_fixed_time_total() {
+        let mut fixed = FixedTime::new(Duration::from_millis(10));
+        fixed.step_count = 3;
+        assert_eq!(fixed.total(), Duration::from_millis(30));
+
+        // The count beyond `u32` isn't truncated.
+        fixed.step_count = u32::MAX as u64 + 1;
+        assert_eq!(
+            fixed.total(),
+            Duration::from_millis(10 * (u32::MAX as u64 + 1))
+        );
+
+        fixed.step = Duration::from_secs(u64::MAX);
+        fixed.step_count = 2;
+        assert_eq!(fixed.total(), SATURATED);
+
+        // The huge step doesn't overflow the accumulated time.
+        let mut fixed = FixedTime::new(Duration::from_secs(u64::MAX));
+        fixed.accumulate(Duration::from_secs(1));
+        fixed.accumulate(SATURATED);
+        assert!(fixed.expend());
+        assert!(!fixed.expend());
+    }
+
     #[test]
     fn test_diagnostic_timer_format() {
         let mut profile_timer = DiagnosticTimer::now();
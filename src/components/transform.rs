@@ -126,6 +126,18 @@ impl Transform2D {
             .transform_point(p2)
     }
 
+    /// Combine `self`(transformation of parent) with `local`(transformation relative to parent).
+    ///
+    /// NOTE: The scale of parent is applied along the axes of child, so the shear caused by
+    /// rotated child under non-uniform scaled parent is discarded.
+    pub fn compose(&self, local: &Transform2D) -> Transform2D {
+        Transform2D {
+            position: self.position + self.rotation * self.scale.component_mul(&local.position),
+            rotation: self.rotation * local.rotation,
+            scale: self.scale.component_mul(&local.scale),
+        }
+    }
+
     fn normal_or_min(num: f32) -> f32 {
         if num.is_normal() {
             num
@@ -146,7 +158,7 @@ unsafe impl bytemuck::Pod for Transform2D {}
 
 #[cfg(test)]
 mod tests {
-    use crate::nalgebra::Matrix3;
+    use crate::nalgebra::{Matrix3, Vector2};
 
     use super::Transform2D;
     use std::assert_eq;
@@ -178,4 +190,16 @@ mod tests {
 
         assert_eq!(t.to_homogeneous(), m_t * m_r * m_s);
     }
+
+    #[test]
+    fn check_compose() {
+        let parent = Transform2D::new(10.0, 0.0, 90.0, 2.0, 2.0);
+        let local = Transform2D::new(1.0, 0.0, 45.0, 3.0, 1.0);
+
+        let world = parent.compose(&local);
+
+        approx::assert_relative_eq!(world.position, Vector2::new(10.0, 2.0), epsilon = 1e-5);
+        approx::assert_relative_eq!(world.angle(), 135.0, epsilon = 1e-4);
+        approx::assert_relative_eq!(world.scale, Vector2::new(6.0, 2.0));
+    }
 }
@@ -0,0 +1,262 @@
+use crate::legion::Resources;
+
+use std::{any::TypeId, collections::VecDeque, marker::PhantomData};
+
+/// The queue of the events of type `T`, which is a resource registered by
+/// `AppBuilder::add_event()`.
+///
+/// The events are double-buffered: an event is kept for the frame it's sent and the next one,
+/// so the systems running before the sender still read it in the next frame.
+///
+/// ```
+/// use yam::Events;
+///
+/// let mut events = Events::<u32>::new();
+/// let mut reader = events.reader();
+///
+/// events.writer().send(1);
+/// assert_eq!(reader.iter(&events).collect::<Vec<_>>(), [&1]);
+/// assert_eq!(reader.iter(&events).count(), 0);
+/// ```
+#[derive(Debug)]
+pub struct Events<T> {
+    // the events of the last frame and this frame.
+    events: VecDeque<T>,
+    // the id of the first event in `events`.
+    start_id: usize,
+    // the number of the events sent in the last frame.
+    last_frame_len: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            events: Default::default(),
+            start_id: 0,
+            last_frame_len: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.push_back(event);
+    }
+
+    pub fn send_batch<I: IntoIterator<Item = T>>(&mut self, events: I) {
+        self.events.extend(events);
+    }
+
+    /// Return a writer to send events, e.g. in a system which gets `&mut Events<T>`.
+    pub fn writer(&mut self) -> EventWriter<'_, T> {
+        EventWriter { events: self }
+    }
+
+    /// Return a reader which only reads the events sent after now.
+    pub fn reader(&self) -> EventReader<T> {
+        EventReader {
+            cursor: self.end_id(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Return the number of the events in the buffers.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Drop all events, the readers skip them.
+    pub fn clear(&mut self) {
+        self.start_id = self.end_id();
+        self.last_frame_len = 0;
+        self.events.clear();
+    }
+
+    /// Drop the events of the last frame, called once per frame by the `App`.
+    pub fn update(&mut self) {
+        self.events.drain(..self.last_frame_len);
+        self.start_id += self.last_frame_len;
+        self.last_frame_len = self.events.len();
+    }
+
+    fn end_id(&self) -> usize {
+        self.start_id + self.events.len()
+    }
+}
+
+/// Send the events to `Events<T>`.
+pub struct EventWriter<'a, T> {
+    events: &'a mut Events<T>,
+}
+
+impl<'a, T> EventWriter<'a, T> {
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    pub fn send_batch<I: IntoIterator<Item = T>>(&mut self, events: I) {
+        self.events.send_batch(events);
+    }
+}
+
+/// Read the events of `Events<T>` which haven't been read by this reader.
+///
+/// It's a cursor without the events, so it can be kept as the `#[state]` of a system.
+///
+/// NOTE: The default reader reads all events in the buffers, `Events::reader()` only reads the
+/// events sent after it's created.
+#[derive(Debug)]
+pub struct EventReader<T> {
+    // the id of the next event to read.
+    cursor: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self {
+            cursor: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> EventReader<T> {
+    /// Iterate the unread events, and mark them as read.
+    ///
+    /// The events not read in two frames are missed.
+    pub fn iter<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let skip = self.cursor.saturating_sub(events.start_id);
+        self.cursor = events.end_id();
+
+        events.events.iter().skip(skip)
+    }
+
+    /// Return the number of the unread events.
+    pub fn len(&self, events: &Events<T>) -> usize {
+        events.end_id() - self.cursor.max(events.start_id).min(events.end_id())
+    }
+
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+}
+
+/// The type-erased `Events<T>`, inserted and updated by the `App`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EventChannel {
+    type_id: TypeId,
+    insert: fn(&mut Resources),
+    update: fn(&Resources),
+}
+
+impl EventChannel {
+    pub(crate) fn of<T: Send + Sync + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            insert: |resources| resources.insert::<Events<T>>(Events::new()),
+            update: |resources| {
+                if let Some(mut events) = resources.get_mut::<Events<T>>() {
+                    events.update();
+                }
+            },
+        }
+    }
+
+    pub(crate) fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub(crate) fn insert(&self, resources: &mut Resources) {
+        (self.insert)(resources);
+    }
+
+    pub(crate) fn update(&self, resources: &Resources) {
+        (self.update)(resources);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{AppBuilder, AppStageBuilder, HeadlessMode};
+
+    #[test]
+    fn test_events_double_buffer() {
+        let mut events = Events::<u32>::new();
+        let mut late = EventReader::default();
+        let mut reader = events.reader();
+
+        events.send_batch(vec![0, 1]);
+        events.update();
+        events.send(2);
+
+        assert_eq!(reader.len(&events), 3);
+        assert_eq!(reader.iter(&events).copied().collect::<Vec<_>>(), [0, 1, 2]);
+        assert!(reader.is_empty(&events));
+
+        events.update();
+        events.writer().send(3);
+
+        assert_eq!(reader.iter(&events).copied().collect::<Vec<_>>(), [3]);
+        // the events of two frames ago are dropped.
+        assert_eq!(late.iter(&events).copied().collect::<Vec<_>>(), [2, 3]);
+
+        events.clear();
+        assert!(late.is_empty(&events));
+        assert!(events.is_empty());
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Ping(u32);
+
+    #[derive(Default)]
+    struct Received(Vec<u32>);
+
+    #[test]
+    fn test_events_across_stages() {
+        let mut reader = EventReader::<Ping>::default();
+        let mut frame = 0;
+
+        // NOTE: The reader runs before the writer, it reads the events in next frame.
+        AppBuilder::new()
+            .add_stage_builder(
+                AppStageBuilder::new(String::from("reader")).add_thread_local_fn_process(
+                    move |_, resources| {
+                        let pings: Vec<u32> = {
+                            let events = resources.get::<Events<Ping>>().unwrap();
+                            reader.iter(&events).map(|ping| ping.0).collect()
+                        };
+
+                        resources.get_mut_or_default::<Received>().0.extend(pings);
+                    },
+                ),
+            )
+            .unwrap()
+            .add_stage_builder(
+                AppStageBuilder::new(String::from("writer"))
+                    .add_thread_local_fn_process(move |_, resources| {
+                        frame += 1;
+
+                        let mut events = resources.get_mut::<Events<Ping>>().unwrap();
+                        events.writer().send(Ping(frame));
+                    })
+                    .add_thread_local_fn_destroy(|_, resources| {
+                        assert_eq!(resources.get::<Received>().unwrap().0, [1, 2, 3]);
+                    }),
+            )
+            .unwrap()
+            .add_event::<Ping>()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(4));
+    }
+}
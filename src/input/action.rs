@@ -0,0 +1,379 @@
+use super::{Input, KeyCode, MouseButton};
+
+use crate::misc::json::{self, JsonError};
+
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, path::Path};
+
+/// A physical input which can trigger an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// The wheel scrolls up in this frame.
+    WheelUp,
+    /// The wheel scrolls down in this frame.
+    WheelDown,
+    /// The wheel scrolls left in this frame.
+    WheelLeft,
+    /// The wheel scrolls right in this frame.
+    WheelRight,
+}
+
+impl Binding {
+    /// The button is held, including the frame it's just pressed.
+    fn down(&self, input: &Input) -> bool {
+        match *self {
+            Binding::Key(keycode) => {
+                input.keyboard.pressed(keycode) || input.keyboard.just_pressed(keycode)
+            }
+            Binding::Mouse(button) => {
+                input.mouse.pressed(button) || input.mouse.just_pressed(button)
+            }
+            _ => self.just_pressed(input),
+        }
+    }
+
+    /// The button is held but not in the frame it's just pressed.
+    fn pressed(&self, input: &Input) -> bool {
+        match *self {
+            Binding::Key(keycode) => input.keyboard.pressed(keycode),
+            Binding::Mouse(button) => input.mouse.pressed(button),
+            // NOTE: The wheel has no hold state.
+            _ => false,
+        }
+    }
+
+    fn just_pressed(&self, input: &Input) -> bool {
+        let (wx, wy) = input.mouse.mouse_wheel_scroll();
+
+        match *self {
+            Binding::Key(keycode) => input.keyboard.just_pressed(keycode),
+            Binding::Mouse(button) => input.mouse.just_pressed(button),
+            Binding::WheelUp => wy > 0.0,
+            Binding::WheelDown => wy < 0.0,
+            Binding::WheelLeft => wx < 0.0,
+            Binding::WheelRight => wx > 0.0,
+        }
+    }
+
+    fn just_released(&self, input: &Input) -> bool {
+        match *self {
+            Binding::Key(keycode) => input.keyboard.just_released(keycode),
+            Binding::Mouse(button) => input.mouse.just_released(button),
+            _ => false,
+        }
+    }
+}
+
+/// A physical input which produces a value in `[-1.0, 1.0]`(buttons) or a motion(wheel and mouse).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// `-1.0` when `negative` is held, `1.0` when `positive` is held, `0.0` when both or none.
+    Buttons {
+        negative: Binding,
+        positive: Binding,
+    },
+    /// The horizontal scroll of wheel or trackpad in this frame(in lines).
+    WheelX,
+    /// The vertical scroll of wheel or trackpad in this frame(in lines).
+    WheelY,
+    /// The horizontal motion of mouse in this frame(in screen space).
+    MouseMotionX,
+    /// The vertical motion of mouse in this frame(in screen space).
+    MouseMotionY,
+}
+
+impl AxisBinding {
+    fn value(&self, input: &Input) -> f32 {
+        match self {
+            AxisBinding::Buttons { negative, positive } => {
+                let neg = if negative.down(input) { 1.0 } else { 0.0 };
+                let pos = if positive.down(input) { 1.0 } else { 0.0 };
+
+                pos - neg
+            }
+            AxisBinding::WheelX => input.mouse.mouse_wheel_scroll().0,
+            AxisBinding::WheelY => input.mouse.mouse_wheel_scroll().1,
+            AxisBinding::MouseMotionX => input.mouse.mouse_motion_in_ss().0,
+            AxisBinding::MouseMotionY => input.mouse.mouse_motion_in_ss().1,
+        }
+    }
+}
+
+/// A pair of `AxisBinding` which produces a 2D value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Axis2DBinding {
+    pub x: AxisBinding,
+    pub y: AxisBinding,
+}
+
+/// Map named actions and axes to physical inputs, so systems don't need to hardcode keys.
+///
+/// An action or axis can have multiple bindings, and the bindings can be changed at runtime
+/// or loaded from a json file.
+///
+/// ```
+/// use yam::{ActionMap, AxisBinding, Binding, KeyCode, MouseButton};
+///
+/// let mut actions = ActionMap::new();
+///
+/// actions.bind("fire", Binding::Key(KeyCode::Space));
+/// actions.bind("fire", Binding::Mouse(MouseButton::Left));
+/// actions.bind_axis(
+///     "move_x",
+///     AxisBinding::Buttons {
+///         negative: Binding::Key(KeyCode::A),
+///         positive: Binding::Key(KeyCode::D),
+///     },
+/// );
+///
+/// assert_eq!(actions.bindings("fire").len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap {
+    #[serde(default)]
+    actions: HashMap<String, Vec<Binding>>,
+    #[serde(default)]
+    axes: HashMap<String, Vec<AxisBinding>>,
+    #[serde(default)]
+    axes2d: HashMap<String, Vec<Axis2DBinding>>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the bindings from a json file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, JsonError> {
+        json::load(path.as_ref())
+    }
+
+    /// Save the bindings to a json file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), JsonError> {
+        json::save(path.as_ref(), &self.to_json()?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        json::from_json(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, JsonError> {
+        json::to_json(self, true)
+    }
+
+    /// Add a binding to the action.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_owned()).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Remove a binding from the action.
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Replace all bindings of the action.
+    pub fn rebind(&mut self, action: &str, bindings: &[Binding]) {
+        self.actions.insert(action.to_owned(), bindings.to_vec());
+    }
+
+    /// Return the bindings of the action, empty if the action does not exist.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |bindings| bindings)
+    }
+
+    /// Add a binding to the 1D axis.
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.to_owned()).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replace all bindings of the 1D axis.
+    pub fn rebind_axis(&mut self, axis: &str, bindings: &[AxisBinding]) {
+        self.axes.insert(axis.to_owned(), bindings.to_vec());
+    }
+
+    /// Return the bindings of the 1D axis, empty if the axis does not exist.
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], |bindings| bindings)
+    }
+
+    /// Add a binding to the 2D axis.
+    pub fn bind_axis2d(&mut self, axis: &str, binding: Axis2DBinding) {
+        let bindings = self.axes2d.entry(axis.to_owned()).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replace all bindings of the 2D axis.
+    pub fn rebind_axis2d(&mut self, axis: &str, bindings: &[Axis2DBinding]) {
+        self.axes2d.insert(axis.to_owned(), bindings.to_vec());
+    }
+
+    /// Return the bindings of the 2D axis, empty if the axis does not exist.
+    pub fn axis2d_bindings(&self, axis: &str) -> &[Axis2DBinding] {
+        self.axes2d.get(axis).map_or(&[], |bindings| bindings)
+    }
+
+    /// Remove the action or axis and all its bindings.
+    pub fn remove(&mut self, name: &str) {
+        self.actions.remove(name);
+        self.axes.remove(name);
+        self.axes2d.remove(name);
+    }
+
+    /// Detect whether the action has been pressed.
+    pub fn pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action).iter().any(|b| b.pressed(input))
+    }
+
+    /// Detect whether the action has been released.
+    pub fn released(&self, input: &Input, action: &str) -> bool {
+        !self.bindings(action).iter().any(|b| b.down(input))
+    }
+
+    /// Detect whether the action has just been pressed.
+    ///
+    /// It's `false` if another binding of the action has been held.
+    pub fn just_pressed(&self, input: &Input, action: &str) -> bool {
+        let bindings = self.bindings(action);
+
+        bindings.iter().any(|b| b.just_pressed(input)) && !bindings.iter().any(|b| b.pressed(input))
+    }
+
+    /// Detect whether the action has just been released.
+    ///
+    /// It's `false` if another binding of the action is still held.
+    pub fn just_released(&self, input: &Input, action: &str) -> bool {
+        let bindings = self.bindings(action);
+
+        bindings.iter().any(|b| b.just_released(input)) && !bindings.iter().any(|b| b.down(input))
+    }
+
+    /// Return the value of the 1D axis, `0.0` if the axis does not exist.
+    ///
+    /// The value of the binding with the largest magnitude wins.
+    pub fn value(&self, input: &Input, axis: &str) -> f32 {
+        self.axis_bindings(axis)
+            .iter()
+            .map(|b| b.value(input))
+            .fold(0.0, |acc, v| if v.abs() > acc.abs() { v } else { acc })
+    }
+
+    /// Return the value of the 2D axis, `(0.0, 0.0)` if the axis does not exist.
+    ///
+    /// The value of the binding with the largest magnitude wins.
+    pub fn value2d(&self, input: &Input, axis: &str) -> (f32, f32) {
+        self.axis2d_bindings(axis)
+            .iter()
+            .map(|b| (b.x.value(input), b.y.value(input)))
+            .fold((0.0, 0.0), |acc, v| {
+                if v.0 * v.0 + v.1 * v.1 > acc.0 * acc.0 + acc.1 * acc.1 {
+                    v
+                } else {
+                    acc
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ButtonState;
+    use super::*;
+
+    #[test]
+    fn test_action_map() {
+        let mut actions = ActionMap::new();
+
+        actions.bind("jump", Binding::Key(KeyCode::Space));
+        actions.bind("jump", Binding::Key(KeyCode::W));
+        actions.bind("zoom_in", Binding::WheelUp);
+        actions.bind_axis(
+            "move_x",
+            AxisBinding::Buttons {
+                negative: Binding::Key(KeyCode::A),
+                positive: Binding::Key(KeyCode::D),
+            },
+        );
+        actions.bind_axis("zoom", AxisBinding::WheelY);
+
+        let mut input = Input::new();
+
+        input
+            .keyboard
+            .key_button_state
+            .insert(KeyCode::W, ButtonState::JustPressed);
+        input
+            .keyboard
+            .key_button_state
+            .insert(KeyCode::A, ButtonState::Pressed);
+        input.mouse.mouse_wheel_motion = (0.0, 2.0);
+
+        assert!(actions.just_pressed(&input, "jump"));
+        assert!(!actions.pressed(&input, "jump"));
+        assert!(actions.just_pressed(&input, "zoom_in"));
+        assert_eq!(actions.value(&input, "move_x"), -1.0);
+        assert_eq!(actions.value(&input, "zoom"), 2.0);
+        assert!(!actions.just_pressed(&input, "not_exist"));
+
+        // `Space` is held, so pressing `W` doesn't trigger `jump` again.
+        input
+            .keyboard
+            .key_button_state
+            .insert(KeyCode::Space, ButtonState::Pressed);
+        assert!(!actions.just_pressed(&input, "jump"));
+        assert!(actions.pressed(&input, "jump"));
+
+        // Rebinding at runtime.
+        actions.rebind("jump", &[Binding::Mouse(MouseButton::Right)]);
+        assert!(actions.released(&input, "jump"));
+    }
+
+    #[test]
+    fn test_action_map_json() {
+        let json = r#"{
+            "actions": { "fire": [{ "Key": "Space" }, { "Mouse": "Left" }, "WheelDown"] },
+            "axes2d": {
+                "move": [{
+                    "x": { "Buttons": { "negative": { "Key": "A" }, "positive": { "Key": "D" } } },
+                    "y": "WheelY"
+                }]
+            }
+        }"#;
+
+        let actions = ActionMap::from_json(json).unwrap();
+
+        assert_eq!(
+            actions.bindings("fire"),
+            &[
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::WheelDown
+            ]
+        );
+        assert_eq!(actions.axis2d_bindings("move").len(), 1);
+        assert_eq!(
+            ActionMap::from_json(&actions.to_json().unwrap()).unwrap(),
+            actions
+        );
+
+        assert!(matches!(
+            ActionMap::from_json("{ \"actions\": 1 }"),
+            Err(JsonError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use super::{GamepadAxis, GamepadButton, GamepadId, KeyCode, Modifiers, MouseButton, TouchPhase};
+
+use serde::{Deserialize, Serialize};
+
+use winit::event::{
+    DeviceEvent, ElementState, KeyboardInput, MouseScrollDelta, Touch, WindowEvent,
+};
+
+/// An input event which is consumed by `Input`.
+///
+/// Unlike the events of `winit`, it doesn't carry window or device ids, so it can be serialized,
+/// recorded and played back.
+///
+/// NOTE: `Events<InputEvent>` only carries the input of the primary window, the input of extra
+/// windows is read by `Windows::input()`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// The wheel scrolls in lines.
+    MouseWheel {
+        x: f32,
+        y: f32,
+    },
+    /// The wheel or trackpad scrolls in pixels.
+    MouseWheelPixels {
+        x: f32,
+        y: f32,
+        phase: TouchPhase,
+    },
+    /// The raw motion of mouse(in screen space).
+    MouseMotion {
+        dx: f32,
+        dy: f32,
+    },
+    /// The cursor moves to the position(in screen space).
+    CursorMoved {
+        x: f32,
+        y: f32,
+    },
+    CursorEntered,
+    CursorLeft,
+    Key {
+        keycode: KeyCode,
+        pressed: bool,
+    },
+    /// A character is typed, including the characters committed by IME.
+    Char(char),
+    /// The state of modifier keys changes.
+    Modifiers(Modifiers),
+    GamepadConnected(GamepadId),
+    GamepadDisconnected(GamepadId),
+    GamepadButton {
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    /// The raw value of the gamepad axis, without deadzone.
+    GamepadAxis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+    /// A finger touches, moves on or leaves the screen, the position is in screen space.
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+    },
+    /// Release all buttons and cancel all touches, to prevent input freeze bug when the window
+    /// is resized or moved, or the app is suspended.
+    ReleaseAll,
+}
+
+impl InputEvent {
+    /// Convert the `WindowEvent` of `winit`, return `None` if `Input` doesn't care about it.
+    pub(crate) fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match *event {
+            WindowEvent::MouseInput { button, state, .. } => Some(InputEvent::MouseButton {
+                button,
+                pressed: state == ElementState::Pressed,
+            }),
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::LineDelta(x, y),
+                ..
+            } => Some(InputEvent::MouseWheel { x, y }),
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(position),
+                phase,
+                ..
+            } => Some(InputEvent::MouseWheelPixels {
+                x: position.x as f32,
+                y: position.y as f32,
+                phase,
+            }),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved {
+                x: position.x as f32,
+                y: position.y as f32,
+            }),
+            WindowEvent::CursorEntered { .. } => Some(InputEvent::CursorEntered),
+            WindowEvent::CursorLeft { .. } => Some(InputEvent::CursorLeft),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(keycode),
+                        ..
+                    },
+                ..
+            } => Some(InputEvent::Key {
+                keycode,
+                pressed: state == ElementState::Pressed,
+            }),
+            WindowEvent::Touch(Touch {
+                id,
+                phase,
+                location,
+                ..
+            }) => Some(InputEvent::Touch {
+                id,
+                phase,
+                x: location.x as f32,
+                y: location.y as f32,
+            }),
+            WindowEvent::ReceivedCharacter(c) => Some(InputEvent::Char(c)),
+            WindowEvent::ModifiersChanged(modifiers) => Some(InputEvent::Modifiers(modifiers)),
+            WindowEvent::Resized(_) | WindowEvent::Moved(_) => Some(InputEvent::ReleaseAll),
+            _ => None,
+        }
+    }
+
+    /// Convert the `DeviceEvent` of `winit`, return `None` if `Input` doesn't care about it.
+    pub(crate) fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match *event {
+            DeviceEvent::MouseMotion { delta } => Some(InputEvent::MouseMotion {
+                dx: delta.0 as f32,
+                dy: delta.1 as f32,
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,531 @@
+use super::{ButtonState, InputEvent};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The identity of a connected gamepad, it's given by the `GamepadBackend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GamepadId(pub u32);
+
+/// The buttons of gamepad, named by the layout of xbox controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    /// `A` on xbox controller, `Cross` on playstation controller.
+    South,
+    /// `B` on xbox controller, `Circle` on playstation controller.
+    East,
+    /// `Y` on xbox controller, `Triangle` on playstation controller.
+    North,
+    /// `X` on xbox controller, `Square` on playstation controller.
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftThumb,
+    RightThumb,
+    Select,
+    Start,
+    Mode,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// The analog axes of gamepad.
+///
+/// The sticks range in `[-1.0, 1.0]`, up and right are positive. The triggers range in
+/// `[0.0, 1.0]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// The source of gamepad events, like a wrapper of `gilrs` or `MockGamepadBackend`.
+pub trait GamepadBackend: Send + Sync {
+    /// Push the gamepad events happened since last poll to `evts`.
+    fn poll(&mut self, evts: &mut Vec<InputEvent>);
+
+    /// Rumble the gamepad with the strength(in `[0.0, 1.0]`) for the duration, return `false`
+    /// if the gamepad doesn't support it.
+    fn rumble(&mut self, _id: GamepadId, _strength: f32, _duration: Duration) -> bool {
+        false
+    }
+
+    /// Set the function waking up the `App`, it's called by the backend polling on another
+    /// thread when the events come, so the gamepads can wake up the `App` in low power mode.
+    fn set_waker(&mut self, _waker: Arc<dyn Fn() + Send + Sync>) {}
+}
+
+pub const DEFAULT_STICK_DEADZONE: f32 = 0.15;
+pub const DEFAULT_TRIGGER_DEADZONE: f32 = 0.05;
+
+/// All connected gamepads.
+///
+/// NOTE: The `App` polls the real gamepads by `GilrsBackend`(the optional feature `gilrs`), except
+/// in headless mode. Replace it by `Self::set_backend()`.
+pub struct Gamepads {
+    gamepads: HashMap<GamepadId, Gamepad>,
+    just_connected: Vec<GamepadId>,
+    just_disconnected: Vec<GamepadId>,
+
+    stick_deadzone: f32,
+    trigger_deadzone: f32,
+
+    backend: Option<Box<dyn GamepadBackend>>,
+    waker: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl Gamepads {
+    pub(crate) fn new() -> Self {
+        Self {
+            gamepads: HashMap::with_capacity(4),
+            just_connected: Vec::with_capacity(4),
+            just_disconnected: Vec::with_capacity(4),
+
+            stick_deadzone: DEFAULT_STICK_DEADZONE,
+            trigger_deadzone: DEFAULT_TRIGGER_DEADZONE,
+
+            backend: None,
+            waker: None,
+        }
+    }
+
+    /// Set the source of gamepad events, the gamepads of previous backend keep connected until
+    /// they are disconnected by the new backend.
+    pub fn set_backend<B: GamepadBackend + 'static>(&mut self, mut backend: B) {
+        if let Some(waker) = &self.waker {
+            backend.set_waker(Arc::clone(waker));
+        }
+
+        self.backend = Some(Box::new(backend));
+    }
+
+    /// Poll the real gamepads by `GilrsBackend` if the feature `gilrs` is enabled.
+    pub(crate) fn set_default_backend(&mut self) {
+        #[cfg(feature = "gilrs")]
+        self.set_backend(super::GilrsBackend::new());
+    }
+
+    pub(crate) fn set_waker(&mut self, waker: Arc<dyn Fn() + Send + Sync>) {
+        if let Some(backend) = self.backend.as_mut() {
+            backend.set_waker(Arc::clone(&waker));
+        }
+
+        self.waker = Some(waker);
+    }
+
+    /// Return the gamepad by id, `None` if it's not connected.
+    pub fn get(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.gamepads.get(&id)
+    }
+
+    /// Return the ids of all connected gamepads in ascending order.
+    pub fn ids(&self) -> Vec<GamepadId> {
+        let mut ids: Vec<GamepadId> = self.gamepads.keys().copied().collect();
+        ids.sort_unstable();
+
+        ids
+    }
+
+    /// Return the connected gamepad with the smallest id, useful for single player games.
+    pub fn first(&self) -> Option<&Gamepad> {
+        self.gamepads
+            .iter()
+            .min_by_key(|(id, _)| **id)
+            .map(|(_, gamepad)| gamepad)
+    }
+
+    /// Return the number of connected gamepads.
+    pub fn len(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gamepads.is_empty()
+    }
+
+    /// Return the gamepads connected in this frame.
+    pub fn just_connected(&self) -> &[GamepadId] {
+        &self.just_connected
+    }
+
+    /// Return the gamepads disconnected in this frame.
+    pub fn just_disconnected(&self) -> &[GamepadId] {
+        &self.just_disconnected
+    }
+
+    /// Modifies the deadzones of sticks and triggers, the values in deadzone are treated as zero.
+    ///
+    /// The stick deadzone is radial, so the diagonal motion isn't snapped to axes.
+    pub fn set_deadzones(&mut self, stick: f32, trigger: f32) {
+        self.stick_deadzone = stick.clamp(0.0, 0.99);
+        self.trigger_deadzone = trigger.clamp(0.0, 0.99);
+
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.stick_deadzone = self.stick_deadzone;
+            gamepad.trigger_deadzone = self.trigger_deadzone;
+        }
+    }
+
+    /// Rumble the gamepad, return `false` if there's no backend or the gamepad doesn't support it.
+    pub fn rumble(&mut self, id: GamepadId, strength: f32, duration: Duration) -> bool {
+        match self.backend.as_mut() {
+            Some(backend) if self.gamepads.contains_key(&id) => {
+                backend.rumble(id, strength.clamp(0.0, 1.0), duration)
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn poll(&mut self, evts: &mut Vec<InputEvent>) {
+        if let Some(backend) = self.backend.as_mut() {
+            backend.poll(evts);
+        }
+    }
+
+    pub(super) fn before_apply(&mut self) {
+        self.just_connected.clear();
+        self.just_disconnected.clear();
+
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.before_apply();
+        }
+    }
+
+    pub(super) fn connect(&mut self, id: GamepadId) {
+        if !self.gamepads.contains_key(&id) {
+            self.gamepads.insert(id, self.new_gamepad());
+            self.just_connected.push(id);
+        }
+    }
+
+    pub(super) fn disconnect(&mut self, id: GamepadId) {
+        if self.gamepads.remove(&id).is_some() {
+            self.just_disconnected.push(id);
+        }
+    }
+
+    /// NOTE: The gamepad is connected silently if it's unknown, e.g. a recording is played.
+    pub(super) fn gamepad_mut(&mut self, id: GamepadId) -> &mut Gamepad {
+        if !self.gamepads.contains_key(&id) {
+            self.gamepads.insert(id, self.new_gamepad());
+        }
+
+        self.gamepads.get_mut(&id).unwrap()
+    }
+
+    pub(super) fn release_all(&mut self) {
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.release_all();
+        }
+    }
+
+    fn new_gamepad(&self) -> Gamepad {
+        Gamepad::new(self.stick_deadzone, self.trigger_deadzone)
+    }
+}
+
+pub struct Gamepad {
+    button_state: HashMap<GamepadButton, ButtonState>,
+    axes: HashMap<GamepadAxis, f32>,
+
+    stick_deadzone: f32,
+    trigger_deadzone: f32,
+}
+
+impl Gamepad {
+    fn new(stick_deadzone: f32, trigger_deadzone: f32) -> Self {
+        Self {
+            button_state: HashMap::with_capacity(16),
+            axes: HashMap::with_capacity(6),
+
+            stick_deadzone,
+            trigger_deadzone,
+        }
+    }
+
+    /// Detect whether the gamepad button has been pressed.
+    pub fn pressed(&self, button: GamepadButton) -> bool {
+        match self.button_state.get(&button) {
+            Some(state) => *state == ButtonState::Pressed,
+            None => false,
+        }
+    }
+
+    /// Detect whether the gamepad button has been released.
+    pub fn released(&self, button: GamepadButton) -> bool {
+        match self.button_state.get(&button) {
+            Some(state) => *state == ButtonState::Released,
+            None => true,
+        }
+    }
+
+    /// Detect whether the gamepad button has just been pressed.
+    pub fn just_pressed(&self, button: GamepadButton) -> bool {
+        match self.button_state.get(&button) {
+            Some(state) => *state == ButtonState::JustPressed,
+            None => false,
+        }
+    }
+
+    /// Detect whether the gamepad button has just been released.
+    pub fn just_released(&self, button: GamepadButton) -> bool {
+        match self.button_state.get(&button) {
+            Some(state) => *state == ButtonState::JustReleased,
+            None => false,
+        }
+    }
+
+    /// Return the value of the axis with deadzone applied.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::LeftStickX => self.left_stick().0,
+            GamepadAxis::LeftStickY => self.left_stick().1,
+            GamepadAxis::RightStickX => self.right_stick().0,
+            GamepadAxis::RightStickY => self.right_stick().1,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                let value = self.raw_axis(axis);
+
+                if value <= self.trigger_deadzone {
+                    0.0
+                } else {
+                    ((value - self.trigger_deadzone) / (1.0 - self.trigger_deadzone)).min(1.0)
+                }
+            }
+        }
+    }
+
+    /// Return the value of the axis reported by the backend, without deadzone.
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// Return the position of the left stick with deadzone applied.
+    pub fn left_stick(&self) -> (f32, f32) {
+        self.stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    /// Return the position of the right stick with deadzone applied.
+    pub fn right_stick(&self) -> (f32, f32) {
+        self.stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    fn stick(&self, x: GamepadAxis, y: GamepadAxis) -> (f32, f32) {
+        let (x, y) = (self.raw_axis(x), self.raw_axis(y));
+        let magnitude = (x * x + y * y).sqrt();
+
+        if magnitude <= self.stick_deadzone {
+            (0.0, 0.0)
+        } else {
+            // NOTE: Rescale the magnitude from `[deadzone, 1.0]` to `[0.0, 1.0]`.
+            let scale = ((magnitude - self.stick_deadzone) / (1.0 - self.stick_deadzone)).min(1.0)
+                / magnitude;
+
+            (x * scale, y * scale)
+        }
+    }
+
+    pub(super) fn set_button(&mut self, button: GamepadButton, pressed: bool) {
+        self.button_state.insert(
+            button,
+            if pressed {
+                ButtonState::JustPressed
+            } else {
+                ButtonState::JustReleased
+            },
+        );
+    }
+
+    pub(super) fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes.insert(axis, value);
+    }
+
+    fn before_apply(&mut self) {
+        for bs in self.button_state.values_mut() {
+            match *bs {
+                ButtonState::JustPressed => *bs = ButtonState::Pressed,
+                ButtonState::JustReleased => *bs = ButtonState::Released,
+                _ => {}
+            }
+        }
+    }
+
+    fn release_all(&mut self) {
+        for (_, bs) in self.button_state.iter_mut() {
+            *bs = ButtonState::Released;
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockShared {
+    events: Vec<InputEvent>,
+    next_id: u32,
+    rumbles: HashMap<GamepadId, (f32, Duration)>,
+}
+
+/// A `GamepadBackend` driven by code, it's useful for testing without hardware.
+///
+/// ```
+/// use yam::{GamepadButton, MockGamepadBackend};
+///
+/// let backend = MockGamepadBackend::new();
+/// // input.gamepads.set_backend(backend.clone());
+///
+/// let gamepad = backend.connect();
+/// gamepad.press(GamepadButton::South);
+/// ```
+#[derive(Clone, Default)]
+pub struct MockGamepadBackend {
+    shared: Arc<Mutex<MockShared>>,
+}
+
+impl MockGamepadBackend {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Connect a new mock gamepad, the event is sent by the next poll.
+    pub fn connect(&self) -> MockGamepad {
+        let mut shared = self.shared.lock().expect("ERR: mock gamepad poisoned");
+
+        let id = GamepadId(shared.next_id);
+        shared.next_id += 1;
+        shared.events.push(InputEvent::GamepadConnected(id));
+
+        MockGamepad {
+            id,
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn poll(&mut self, evts: &mut Vec<InputEvent>) {
+        let mut shared = self.shared.lock().expect("ERR: mock gamepad poisoned");
+
+        evts.append(&mut shared.events);
+    }
+
+    fn rumble(&mut self, id: GamepadId, strength: f32, duration: Duration) -> bool {
+        let mut shared = self.shared.lock().expect("ERR: mock gamepad poisoned");
+        shared.rumbles.insert(id, (strength, duration));
+
+        true
+    }
+}
+
+/// A virtual gamepad connected by `MockGamepadBackend`.
+pub struct MockGamepad {
+    id: GamepadId,
+    shared: Arc<Mutex<MockShared>>,
+}
+
+impl MockGamepad {
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    pub fn press(&self, button: GamepadButton) {
+        self.send(InputEvent::GamepadButton {
+            id: self.id,
+            button,
+            pressed: true,
+        });
+    }
+
+    pub fn release(&self, button: GamepadButton) {
+        self.send(InputEvent::GamepadButton {
+            id: self.id,
+            button,
+            pressed: false,
+        });
+    }
+
+    pub fn set_axis(&self, axis: GamepadAxis, value: f32) {
+        self.send(InputEvent::GamepadAxis {
+            id: self.id,
+            axis,
+            value,
+        });
+    }
+
+    /// Return the last rumble requested to the gamepad.
+    pub fn rumble(&self) -> Option<(f32, Duration)> {
+        let shared = self.shared.lock().expect("ERR: mock gamepad poisoned");
+
+        shared.rumbles.get(&self.id).copied()
+    }
+
+    pub fn disconnect(self) {
+        self.send(InputEvent::GamepadDisconnected(self.id));
+    }
+
+    fn send(&self, evt: InputEvent) {
+        let mut shared = self.shared.lock().expect("ERR: mock gamepad poisoned");
+        shared.events.push(evt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Input;
+    use super::*;
+    use crate::misc::coordinates::Transformation;
+
+    #[test]
+    fn test_mock_gamepad() {
+        let trf = Transformation::default();
+        let mut input = Input::new();
+        let mut evts = Vec::new();
+
+        let backend = MockGamepadBackend::new();
+        input.gamepads.set_backend(backend.clone());
+
+        let gamepad = backend.connect();
+        gamepad.press(GamepadButton::South);
+        gamepad.set_axis(GamepadAxis::LeftStickX, 0.1);
+        gamepad.set_axis(GamepadAxis::LeftStickY, 0.1);
+        gamepad.set_axis(GamepadAxis::RightStickX, 1.0);
+        gamepad.set_axis(GamepadAxis::LeftTrigger, 0.525);
+
+        input.gamepads.poll(&mut evts);
+        input.apply(&evts, &trf);
+        evts.clear();
+
+        assert_eq!(input.gamepads.just_connected(), &[gamepad.id()]);
+        let pad = input.gamepads.get(gamepad.id()).unwrap();
+        assert!(pad.just_pressed(GamepadButton::South));
+        assert!(pad.released(GamepadButton::East));
+        // NOTE: The magnitude of left stick is in the deadzone.
+        assert_eq!(pad.left_stick(), (0.0, 0.0));
+        assert_eq!(pad.raw_axis(GamepadAxis::LeftStickX), 0.1);
+        assert_eq!(pad.right_stick(), (1.0, 0.0));
+        assert!((pad.axis(GamepadAxis::LeftTrigger) - 0.5).abs() < 1e-6);
+
+        assert!(input
+            .gamepads
+            .rumble(gamepad.id(), 2.0, Duration::from_millis(100)));
+        assert_eq!(gamepad.rumble(), Some((1.0, Duration::from_millis(100))));
+
+        let id = gamepad.id();
+        gamepad.disconnect();
+
+        input.gamepads.poll(&mut evts);
+        input.apply(&evts, &trf);
+
+        assert!(input.gamepads.is_empty());
+        assert_eq!(input.gamepads.just_disconnected(), &[id]);
+        assert!(!input.gamepads.rumble(id, 1.0, Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,329 @@
+use super::{GamepadAxis, GamepadBackend, GamepadButton, GamepadId, InputEvent};
+
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks},
+    Axis, Button, EventType, Gilrs,
+};
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the gamepads are polled by the background thread.
+const POLL_INTERVAL: Duration = Duration::from_millis(4);
+
+type Waker = Arc<dyn Fn() + Send + Sync>;
+
+/// The `GamepadBackend` of the real gamepads, which is used by the `App` if the feature `gilrs` is
+/// enabled.
+///
+/// `gilrs` is polled on a background thread, because it can't be sent between threads. The
+/// events are sent to the backend, and wake up the `App` in low power mode.
+pub struct GilrsBackend {
+    events: Mutex<Receiver<InputEvent>>,
+    rumbles: Mutex<Sender<(GamepadId, f32, Duration)>>,
+
+    // the gamepads supporting force feedback.
+    ff_supported: Arc<Mutex<HashSet<GamepadId>>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl GilrsBackend {
+    /// Start polling the gamepads, the gamepads connected before are sent as connected.
+    pub fn new() -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let (rumble_tx, rumble_rx) = mpsc::channel();
+        let ff_supported: Arc<Mutex<HashSet<GamepadId>>> = Default::default();
+        let waker: Arc<Mutex<Option<Waker>>> = Default::default();
+
+        let worker = Worker {
+            events: event_tx,
+            rumbles: rumble_rx,
+            ff_supported: Arc::clone(&ff_supported),
+            waker: Arc::clone(&waker),
+            ids: HashMap::new(),
+            effects: HashMap::new(),
+        };
+
+        thread::Builder::new()
+            .name(String::from("yam-gilrs"))
+            .spawn(move || worker.run())
+            .expect("ERR: Failed to spawn the gamepad thread.");
+
+        Self {
+            events: Mutex::new(event_rx),
+            rumbles: Mutex::new(rumble_tx),
+            ff_supported,
+            waker,
+        }
+    }
+}
+
+impl Default for GilrsBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, evts: &mut Vec<InputEvent>) {
+        let events = self.events.lock().expect("ERR: gilrs backend poisoned");
+
+        evts.extend(events.try_iter());
+    }
+
+    fn rumble(&mut self, id: GamepadId, strength: f32, duration: Duration) -> bool {
+        let supported = self
+            .ff_supported
+            .lock()
+            .expect("ERR: gilrs backend poisoned")
+            .contains(&id);
+
+        supported
+            && self
+                .rumbles
+                .lock()
+                .expect("ERR: gilrs backend poisoned")
+                .send((id, strength, duration))
+                .is_ok()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        *self.waker.lock().expect("ERR: gilrs backend poisoned") = Some(waker);
+    }
+}
+
+/// The state owned by the background thread.
+struct Worker {
+    events: Sender<InputEvent>,
+    rumbles: Receiver<(GamepadId, f32, Duration)>,
+    ff_supported: Arc<Mutex<HashSet<GamepadId>>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+
+    ids: HashMap<GamepadId, gilrs::GamepadId>,
+    // NOTE: The effect stops when it's dropped, keep the last one of each gamepad.
+    effects: HashMap<GamepadId, Effect>,
+}
+
+impl Worker {
+    fn run(mut self) {
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            // NOTE: The dummy context works without gamepads.
+            Err(gilrs::Error::NotImplemented(gilrs)) => gilrs,
+            Err(err) => {
+                println!("Gamepads are unavailable: {}.", err);
+                return;
+            }
+        };
+
+        // NOTE: The gamepads connected before don't send `Connected`.
+        let connected: Vec<gilrs::GamepadId> = gilrs.gamepads().map(|(id, _)| id).collect();
+        let mut woken = false;
+        for id in connected {
+            woken |= self.connect(&gilrs, id);
+        }
+        if woken {
+            self.wake();
+        }
+
+        loop {
+            loop {
+                match self.rumbles.try_recv() {
+                    Ok((id, strength, duration)) => self.rumble(&mut gilrs, id, strength, duration),
+                    Err(TryRecvError::Empty) => break,
+                    // NOTE: The backend is dropped.
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+
+            let mut woken = false;
+            while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+                let evt = match event {
+                    EventType::Connected => {
+                        woken |= self.connect(&gilrs, id);
+                        continue;
+                    }
+                    EventType::Disconnected => {
+                        let id = to_gamepad_id(id);
+
+                        self.ids.remove(&id);
+                        self.effects.remove(&id);
+                        self.ff_supported
+                            .lock()
+                            .expect("ERR: gilrs backend poisoned")
+                            .remove(&id);
+
+                        Some(InputEvent::GamepadDisconnected(id))
+                    }
+                    EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                        to_gamepad_button(button).map(|button| InputEvent::GamepadButton {
+                            id: to_gamepad_id(id),
+                            button,
+                            pressed: matches!(event, EventType::ButtonPressed(..)),
+                        })
+                    }
+                    // NOTE: The analog triggers are reported as buttons by `gilrs`.
+                    EventType::ButtonChanged(button, value, _) => {
+                        to_trigger_axis(button).map(|axis| InputEvent::GamepadAxis {
+                            id: to_gamepad_id(id),
+                            axis,
+                            value,
+                        })
+                    }
+                    EventType::AxisChanged(axis, value, _) => {
+                        to_gamepad_axis(axis).map(|axis| InputEvent::GamepadAxis {
+                            id: to_gamepad_id(id),
+                            axis,
+                            value,
+                        })
+                    }
+                    _ => None,
+                };
+
+                if let Some(evt) = evt {
+                    if self.events.send(evt).is_err() {
+                        return;
+                    }
+                    woken = true;
+                }
+            }
+
+            if woken {
+                self.wake();
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn connect(&mut self, gilrs: &Gilrs, gilrs_id: gilrs::GamepadId) -> bool {
+        let id = to_gamepad_id(gilrs_id);
+
+        self.ids.insert(id, gilrs_id);
+        if gilrs.gamepad(gilrs_id).is_ff_supported() {
+            self.ff_supported
+                .lock()
+                .expect("ERR: gilrs backend poisoned")
+                .insert(id);
+        }
+
+        self.events.send(InputEvent::GamepadConnected(id)).is_ok()
+    }
+
+    fn rumble(&mut self, gilrs: &mut Gilrs, id: GamepadId, strength: f32, duration: Duration) {
+        let gilrs_id = match self.ids.get(&id) {
+            Some(gilrs_id) => *gilrs_id,
+            None => return,
+        };
+        let ticks = Ticks::from_ms(u32::try_from(duration.as_millis()).unwrap_or(u32::MAX));
+
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: (strength * u16::MAX as f32) as u16,
+                },
+                scheduling: Replay {
+                    play_for: ticks,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .gamepads(&[gilrs_id])
+            .repeat(Repeat::For(ticks))
+            .finish(gilrs);
+
+        if let Ok(effect) = effect {
+            if effect.play().is_ok() {
+                self.effects.insert(id, effect);
+            }
+        }
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self
+            .waker
+            .lock()
+            .expect("ERR: gilrs backend poisoned")
+            .as_ref()
+        {
+            waker();
+        }
+    }
+}
+
+fn to_gamepad_id(id: gilrs::GamepadId) -> GamepadId {
+    let id: usize = id.into();
+
+    GamepadId(id as u32)
+}
+
+fn to_gamepad_button(button: Button) -> Option<GamepadButton> {
+    match button {
+        Button::South => Some(GamepadButton::South),
+        Button::East => Some(GamepadButton::East),
+        Button::North => Some(GamepadButton::North),
+        Button::West => Some(GamepadButton::West),
+        Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+        Button::RightTrigger => Some(GamepadButton::RightBumper),
+        Button::LeftThumb => Some(GamepadButton::LeftThumb),
+        Button::RightThumb => Some(GamepadButton::RightThumb),
+        Button::Select => Some(GamepadButton::Select),
+        Button::Start => Some(GamepadButton::Start),
+        Button::Mode => Some(GamepadButton::Mode),
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None,
+    }
+}
+
+fn to_trigger_axis(button: Button) -> Option<GamepadAxis> {
+    match button {
+        Button::LeftTrigger2 => Some(GamepadAxis::LeftTrigger),
+        Button::RightTrigger2 => Some(GamepadAxis::RightTrigger),
+        _ => None,
+    }
+}
+
+fn to_gamepad_axis(axis: Axis) -> Option<GamepadAxis> {
+    match axis {
+        Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+        Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+        Axis::RightStickX => Some(GamepadAxis::RightStickX),
+        Axis::RightStickY => Some(GamepadAxis::RightStickY),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gilrs_mapping() {
+        assert_eq!(
+            to_gamepad_button(Button::LeftTrigger),
+            Some(GamepadButton::LeftBumper)
+        );
+        assert_eq!(to_gamepad_button(Button::LeftTrigger2), None);
+        assert_eq!(
+            to_trigger_axis(Button::RightTrigger2),
+            Some(GamepadAxis::RightTrigger)
+        );
+        assert_eq!(to_trigger_axis(Button::South), None);
+        assert_eq!(
+            to_gamepad_axis(Axis::RightStickY),
+            Some(GamepadAxis::RightStickY)
+        );
+        assert_eq!(to_gamepad_axis(Axis::DPadX), None);
+    }
+}
@@ -0,0 +1,605 @@
+use crate::{
+    app::{AppBuildError, AppBuilder},
+    misc::coordinates::Transformation,
+    nalgebra::Vector4,
+    plugin::Plugin,
+    window::Window,
+};
+
+use std::collections::{HashMap, HashSet};
+
+mod action;
+mod event;
+mod gamepad;
+#[cfg(feature = "gilrs")]
+mod gilrs_backend;
+mod record;
+mod touch;
+
+pub use action::{ActionMap, Axis2DBinding, AxisBinding, Binding};
+pub use event::InputEvent;
+pub use gamepad::{
+    Gamepad, GamepadAxis, GamepadBackend, GamepadButton, GamepadId, Gamepads, MockGamepad,
+    MockGamepadBackend, DEFAULT_STICK_DEADZONE, DEFAULT_TRIGGER_DEADZONE,
+};
+#[cfg(feature = "gilrs")]
+pub use gilrs_backend::GilrsBackend;
+pub use record::{InputRecorder, InputRecording};
+pub use touch::{Gesture, Touch, Touches, TAP_MAX_DURATION, TAP_SLOP};
+
+pub type KeyCode = winit::event::VirtualKeyCode;
+pub type MouseButton = winit::event::MouseButton;
+pub type Modifiers = winit::event::ModifiersState;
+pub type TouchPhase = winit::event::TouchPhase;
+
+/// The number of pixels treated as one line when normalizing the scroll value.
+pub const PIXELS_PER_LINE: f32 = 20.0;
+
+pub struct Input {
+    pub mouse: Mouse,
+    pub keyboard: Keyboard,
+    pub gamepads: Gamepads,
+    pub touches: Touches,
+}
+
+impl Input {
+    pub(crate) fn new() -> Self {
+        Self {
+            mouse: Mouse::new(),
+            keyboard: Keyboard::new(),
+            gamepads: Gamepads::new(),
+            touches: Touches::new(),
+        }
+    }
+
+    pub(crate) fn apply(&mut self, evts: &[InputEvent], trf: &Transformation) {
+        self.mouse.before_apply();
+        self.keyboard.before_apply();
+        self.gamepads.before_apply();
+        self.touches.before_apply();
+
+        self.mouse.trf = *trf;
+
+        for evt in evts {
+            match *evt {
+                InputEvent::MouseButton { button, pressed } => {
+                    let state = if pressed {
+                        ButtonState::JustPressed
+                    } else {
+                        ButtonState::JustReleased
+                    };
+
+                    self.mouse.mouse_button_state.insert(button, state);
+                }
+                InputEvent::MouseWheel { x, y } => {
+                    self.mouse.mouse_wheel_motion.0 += x;
+                    self.mouse.mouse_wheel_motion.1 += y;
+                }
+                InputEvent::MouseWheelPixels { x, y, phase } => {
+                    self.mouse.mouse_wheel_pixels.0 += x;
+                    self.mouse.mouse_wheel_pixels.1 += y;
+
+                    self.mouse.scroll_gesture = match phase {
+                        TouchPhase::Started | TouchPhase::Moved => true,
+                        TouchPhase::Ended | TouchPhase::Cancelled => false,
+                    };
+                }
+                InputEvent::MouseMotion { dx, dy } => {
+                    self.mouse.mouse_motion = (dx, dy);
+                }
+
+                InputEvent::CursorLeft => {
+                    self.mouse.cursor_state = CursorState::JustLeft;
+                }
+                InputEvent::CursorEntered => {
+                    self.mouse.cursor_state = CursorState::JustEntered;
+                }
+                InputEvent::CursorMoved { x, y } => {
+                    self.mouse.cursor_position_ss = (x, y);
+                }
+
+                InputEvent::Key { keycode, pressed } => {
+                    if let Some(bs) = self.keyboard.key_button_state.get_mut(&keycode) {
+                        match (pressed, *bs) {
+                            // NOTE: The key is held, so it's a repeat event sent by the system.
+                            (true, ButtonState::Pressed) | (true, ButtonState::JustPressed) => {
+                                self.keyboard.repeated_keys.insert(keycode);
+                            }
+                            (true, _) => *bs = ButtonState::JustPressed,
+                            (false, _) => *bs = ButtonState::JustReleased,
+                        }
+                    } else {
+                        self.keyboard.key_button_state.insert(
+                            keycode,
+                            if pressed {
+                                ButtonState::JustPressed
+                            } else {
+                                ButtonState::JustReleased
+                            },
+                        );
+                    }
+                }
+
+                InputEvent::Char(c) => self.keyboard.text.push(c),
+                InputEvent::Modifiers(modifiers) => self.keyboard.modifiers = modifiers,
+
+                InputEvent::GamepadConnected(id) => self.gamepads.connect(id),
+                InputEvent::GamepadDisconnected(id) => self.gamepads.disconnect(id),
+                InputEvent::GamepadButton {
+                    id,
+                    button,
+                    pressed,
+                } => self.gamepads.gamepad_mut(id).set_button(button, pressed),
+                InputEvent::GamepadAxis { id, axis, value } => {
+                    self.gamepads.gamepad_mut(id).set_axis(axis, value)
+                }
+
+                InputEvent::Touch { id, phase, x, y } => self.touches.touch(id, phase, (x, y)),
+
+                InputEvent::ReleaseAll => self.release_all(),
+            }
+        }
+
+        self.touches.after_apply(trf);
+    }
+
+    pub(crate) fn release_all(&mut self) {
+        self.mouse.release_all();
+        self.keyboard.release_all();
+        self.gamepads.release_all();
+        self.touches.cancel_all();
+    }
+}
+
+pub struct Mouse {
+    mouse_motion: (f32, f32),
+    // the wheel motion in lines.
+    mouse_wheel_motion: (f32, f32),
+    // the wheel motion in pixels.
+    mouse_wheel_pixels: (f32, f32),
+    // whether a smooth-scroll gesture is in progress.
+    scroll_gesture: bool,
+    mouse_button_state: HashMap<MouseButton, ButtonState>,
+
+    cursor_state: CursorState,
+    // cursor position in `screen space`.
+    cursor_position_ss: (f32, f32),
+
+    trf: Transformation,
+}
+
+impl Mouse {
+    fn new() -> Self {
+        Self {
+            mouse_motion: (0f32, 0f32),
+            mouse_wheel_motion: (0f32, 0f32),
+            mouse_wheel_pixels: (0f32, 0f32),
+            scroll_gesture: false,
+            mouse_button_state: HashMap::with_capacity(4),
+
+            cursor_state: CursorState::Left,
+            cursor_position_ss: (0f32, 0f32),
+
+            trf: Transformation::default(),
+        }
+    }
+
+    /// Detect whether the mouse button has been pressed.
+    pub fn pressed(&self, button: MouseButton) -> bool {
+        match self.mouse_button_state.get(&button) {
+            Some(state) => *state == ButtonState::Pressed,
+            None => false,
+        }
+    }
+
+    /// Detect whether the mouse button has been released.
+    pub fn released(&self, button: MouseButton) -> bool {
+        match self.mouse_button_state.get(&button) {
+            Some(state) => *state == ButtonState::Released,
+            None => true,
+        }
+    }
+
+    /// Detect whether the mouse button has just been pressed.  
+    pub fn just_pressed(&self, button: MouseButton) -> bool {
+        match self.mouse_button_state.get(&button) {
+            Some(state) => *state == ButtonState::JustPressed,
+            None => false,
+        }
+    }
+
+    /// Detect whether the mouse button has just been released.
+    pub fn just_released(&self, button: MouseButton) -> bool {
+        match self.mouse_button_state.get(&button) {
+            Some(state) => *state == ButtonState::JustReleased,
+            None => false,
+        }
+    }
+
+    /// Detect whether the cursor has been left the window.
+    pub fn cursor_left(&self) -> bool {
+        self.cursor_state == CursorState::Left
+    }
+
+    /// Detect whether the cursor has been entered the window.
+    pub fn cursor_entered(&self) -> bool {
+        self.cursor_state == CursorState::Entered
+    }
+
+    /// Detect whether the cursor has just been left the window.
+    pub fn cursor_just_left(&self) -> bool {
+        self.cursor_state == CursorState::JustLeft
+    }
+
+    /// Detect whether the cursor has just been entered the window.
+    pub fn cursor_just_entered(&self) -> bool {
+        self.cursor_state == CursorState::JustEntered
+    }
+
+    /// Return the position of the cursor in `screen space`.
+    pub fn cursor_position_in_ss(&self) -> (f32, f32) {
+        self.cursor_position_ss
+    }
+
+    /// Return the position of the cursor in `view space`.
+    pub fn cursor_position_in_vs(&self) -> (f32, f32) {
+        let mp_vs = self.trf.mx_s2v()
+            * Vector4::new(
+                self.cursor_position_ss.0,
+                self.cursor_position_ss.1,
+                0.0,
+                1.0,
+            );
+
+        (mp_vs.x, mp_vs.y)
+    }
+
+    /// Return the position of the cursor in `world space`.
+    pub fn cursor_position_in_ws(&self) -> (f32, f32) {
+        let mp_ws = self.trf.mx_s2w()
+            * Vector4::new(
+                self.cursor_position_ss.0,
+                self.cursor_position_ss.1,
+                0.0,
+                1.0,
+            );
+
+        (mp_ws.x, mp_ws.y)
+    }
+
+    /// Return the difference in the position of the mouse between two frames(in screen space).
+    pub fn mouse_motion_in_ss(&self) -> (f32, f32) {
+        self.mouse_motion
+    }
+
+    /// Return the difference in the position of the mouse between two frames(in view space).
+    pub fn mouse_motion_in_vs(&self) -> (f32, f32) {
+        let mm_vs =
+            self.trf.mx_s2v() * Vector4::new(self.mouse_motion.0, self.mouse_motion.1, 0.0, 0.0);
+
+        (mm_vs.x, mm_vs.y)
+    }
+
+    /// Return the difference in the position of the mouse between two frames(in world space).
+    pub fn mouse_motion_in_ws(&self) -> (f32, f32) {
+        let mm_ws =
+            self.trf.mx_s2w() * Vector4::new(self.mouse_motion.0, self.mouse_motion.1, 0.0, 0.0);
+
+        (mm_ws.x, mm_ws.y)
+    }
+
+    /// Return the difference in the wheel position of the mouse between two frames(in lines).
+    ///
+    /// It's always zero when scrolling by trackpads, use `Self::mouse_wheel_scroll()` instead if
+    /// you don't care about the source.
+    pub fn mouse_wheel_motion(&self) -> (f32, f32) {
+        self.mouse_wheel_motion
+    }
+
+    /// Return the difference in the wheel position of the mouse between two frames(in pixels).
+    ///
+    /// It comes from trackpads and high-resolution wheels.
+    pub fn mouse_wheel_pixels(&self) -> (f32, f32) {
+        self.mouse_wheel_pixels
+    }
+
+    /// Return the scroll value between two frames in lines, both the line and the pixel deltas
+    /// are counted, the pixel delta is converted by `PIXELS_PER_LINE`.
+    pub fn mouse_wheel_scroll(&self) -> (f32, f32) {
+        (
+            self.mouse_wheel_motion.0 + self.mouse_wheel_pixels.0 / PIXELS_PER_LINE,
+            self.mouse_wheel_motion.1 + self.mouse_wheel_pixels.1 / PIXELS_PER_LINE,
+        )
+    }
+
+    /// Detect whether a smooth-scroll gesture(like two fingers on trackpad) is in progress.
+    ///
+    /// It keeps `true` from the gesture starts until it ends, even if no scroll in the frame.
+    pub fn scroll_gesture(&self) -> bool {
+        self.scroll_gesture
+    }
+
+    fn before_apply(&mut self) {
+        self.mouse_motion = (0f32, 0f32);
+        self.mouse_wheel_motion = (0f32, 0f32);
+        self.mouse_wheel_pixels = (0f32, 0f32);
+
+        self.cursor_state = match self.cursor_state {
+            CursorState::JustLeft => CursorState::Left,
+            CursorState::JustEntered => CursorState::Entered,
+            _ => self.cursor_state,
+        };
+
+        for bs in self.mouse_button_state.values_mut() {
+            match *bs {
+                ButtonState::JustPressed => *bs = ButtonState::Pressed,
+                ButtonState::JustReleased => *bs = ButtonState::Released,
+                _ => {}
+            };
+        }
+    }
+
+    fn release_all(&mut self) {
+        for (_, bs) in self.mouse_button_state.iter_mut() {
+            *bs = ButtonState::Released;
+        }
+    }
+}
+
+pub struct Keyboard {
+    key_button_state: HashMap<KeyCode, ButtonState>,
+    // the keys sent repeat events in this frame.
+    repeated_keys: HashSet<KeyCode>,
+    // the characters typed in this frame.
+    text: String,
+    modifiers: Modifiers,
+}
+
+impl Keyboard {
+    fn new() -> Self {
+        Self {
+            key_button_state: HashMap::with_capacity(16),
+            repeated_keys: HashSet::with_capacity(4),
+            text: String::with_capacity(16),
+            modifiers: Modifiers::empty(),
+        }
+    }
+
+    /// Detect whether the keyboard button has been pressed.
+    pub fn just_pressed(&self, keycode: KeyCode) -> bool {
+        match self.key_button_state.get(&keycode) {
+            Some(state) => *state == ButtonState::JustPressed,
+            None => false,
+        }
+    }
+
+    /// Detect whether the keyboard button has been released.
+    pub fn just_released(&self, keycode: KeyCode) -> bool {
+        match self.key_button_state.get(&keycode) {
+            Some(state) => *state == ButtonState::JustReleased,
+            None => false,
+        }
+    }
+
+    /// Detect whether the keyboard button has just been pressed.
+    pub fn pressed(&self, keycode: KeyCode) -> bool {
+        match self.key_button_state.get(&keycode) {
+            Some(state) => *state == ButtonState::Pressed,
+            None => false,
+        }
+    }
+
+    /// Detect whether the keyboard button has just been released.
+    pub fn released(&self, keycode: KeyCode) -> bool {
+        match self.key_button_state.get(&keycode) {
+            Some(state) => *state == ButtonState::Released,
+            None => true,
+        }
+    }
+
+    /// Detect whether the keyboard button is held and sends a repeat event in this frame.
+    ///
+    /// The repeat delay and rate are decided by the system, it's useful for text editing.
+    pub fn repeated(&self, keycode: KeyCode) -> bool {
+        self.repeated_keys.contains(&keycode)
+    }
+
+    /// Return the characters typed in this frame, in the order they are typed.
+    ///
+    /// NOTE: The characters committed by IME are included, but the preedit text is not.
+    /// The control characters(like `'\u{8}'` for backspace) are included too, filter them by
+    /// `char::is_control` if you don't want them.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Return the current state of modifier keys.
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Detect whether the shift key is held.
+    pub fn shift(&self) -> bool {
+        self.modifiers.shift()
+    }
+
+    /// Detect whether the ctrl key is held.
+    pub fn ctrl(&self) -> bool {
+        self.modifiers.ctrl()
+    }
+
+    /// Detect whether the alt key is held.
+    pub fn alt(&self) -> bool {
+        self.modifiers.alt()
+    }
+
+    /// Detect whether the logo key(windows/command) is held.
+    pub fn logo(&self) -> bool {
+        self.modifiers.logo()
+    }
+
+    fn before_apply(&mut self) {
+        self.repeated_keys.clear();
+        self.text.clear();
+
+        for bs in self.key_button_state.values_mut() {
+            match *bs {
+                ButtonState::JustPressed => *bs = ButtonState::Pressed,
+                ButtonState::JustReleased => *bs = ButtonState::Released,
+                _ => {}
+            }
+        }
+    }
+
+    fn release_all(&mut self) {
+        for (_, bs) in self.key_button_state.iter_mut() {
+            *bs = ButtonState::Released;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ButtonState {
+    Pressed,
+    Released,
+    JustPressed,
+    JustReleased,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CursorState {
+    Left,
+    Entered,
+    JustLeft,
+    JustEntered,
+}
+
+/// The default plugin of `Input`, `ActionMap`, `InputRecorder` and `Events<InputEvent>`.
+///
+/// NOTE: The real gamepads are polled by `GilrsBackend`(the optional feature `gilrs`) in
+/// `App::run()` only. `Input` and `Events<InputEvent>` only receive the input of the primary
+/// window, each extra window of `Windows` has its own `Input`.
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_resource_with(|resources| {
+            let mut input = Input::new();
+
+            // NOTE: The headless `App` has no `Window`.
+            if resources.contains::<Window>() {
+                input.gamepads.set_default_backend();
+            }
+
+            input
+        })
+        .insert_resource(ActionMap::new())
+        .insert_resource(InputRecorder::new())
+        .insert_event::<InputEvent>();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mouse_wheel() {
+        let trf = Transformation::default();
+        let mut input = Input::new();
+
+        input.apply(
+            &[
+                InputEvent::MouseWheel { x: 0.0, y: 1.0 },
+                InputEvent::MouseWheel { x: 0.0, y: 2.0 },
+                InputEvent::MouseWheelPixels {
+                    x: PIXELS_PER_LINE,
+                    y: -10.0,
+                    phase: TouchPhase::Started,
+                },
+                InputEvent::MouseWheelPixels {
+                    x: 0.0,
+                    y: -10.0,
+                    phase: TouchPhase::Moved,
+                },
+            ],
+            &trf,
+        );
+
+        assert_eq!(input.mouse.mouse_wheel_motion(), (0.0, 3.0));
+        assert_eq!(input.mouse.mouse_wheel_pixels(), (PIXELS_PER_LINE, -20.0));
+        assert_eq!(
+            input.mouse.mouse_wheel_scroll(),
+            (1.0, 3.0 - 20.0 / PIXELS_PER_LINE)
+        );
+        assert!(input.mouse.scroll_gesture());
+
+        // The gesture lasts until it ends, but the deltas are reset every frame.
+        input.apply(&[], &trf);
+        assert_eq!(input.mouse.mouse_wheel_scroll(), (0.0, 0.0));
+        assert!(input.mouse.scroll_gesture());
+
+        input.apply(
+            &[InputEvent::MouseWheelPixels {
+                x: 0.0,
+                y: 0.0,
+                phase: TouchPhase::Ended,
+            }],
+            &trf,
+        );
+        assert!(!input.mouse.scroll_gesture());
+    }
+
+    #[test]
+    fn test_keyboard_text_and_modifiers() {
+        let trf = Transformation::default();
+        let mut input = Input::new();
+
+        input.apply(
+            &[
+                InputEvent::Modifiers(Modifiers::CTRL | Modifiers::SHIFT),
+                InputEvent::Key {
+                    keycode: KeyCode::S,
+                    pressed: true,
+                },
+                InputEvent::Char('y'),
+                InputEvent::Char('\u{7f8a}'),
+            ],
+            &trf,
+        );
+
+        assert!(input.keyboard.ctrl() && input.keyboard.shift());
+        assert!(!input.keyboard.alt() && !input.keyboard.logo());
+        assert!(input.keyboard.just_pressed(KeyCode::S));
+        assert!(!input.keyboard.repeated(KeyCode::S));
+        assert_eq!(input.keyboard.text(), "y\u{7f8a}");
+
+        // The system repeats the held key.
+        let repeat = InputEvent::Key {
+            keycode: KeyCode::S,
+            pressed: true,
+        };
+        input.apply(&[repeat, repeat], &trf);
+
+        assert!(input.keyboard.pressed(KeyCode::S));
+        assert!(input.keyboard.repeated(KeyCode::S));
+        assert_eq!(input.keyboard.text(), "");
+
+        input.apply(
+            &[
+                InputEvent::Modifiers(Modifiers::empty()),
+                InputEvent::Key {
+                    keycode: KeyCode::S,
+                    pressed: false,
+                },
+            ],
+            &trf,
+        );
+
+        assert!(!input.keyboard.ctrl());
+        assert!(!input.keyboard.repeated(KeyCode::S));
+        assert!(input.keyboard.just_released(KeyCode::S));
+    }
+}
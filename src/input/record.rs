@@ -0,0 +1,240 @@
+use super::InputEvent;
+
+use crate::misc::json::{self, JsonError};
+
+use serde::{Deserialize, Serialize};
+
+use std::{mem, path::Path};
+
+/// The input events of every frame, which can be saved to and loaded from a json file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    frames: Vec<Vec<InputEvent>>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a recording from the input events of every frame, useful for gameplay tests.
+    pub fn from_frames(frames: Vec<Vec<InputEvent>>) -> Self {
+        Self { frames }
+    }
+
+    /// Load a recording from a json file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, JsonError> {
+        json::load(path.as_ref())
+    }
+
+    /// Save the recording to a json file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), JsonError> {
+        json::save(path.as_ref(), &self.to_json()?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        json::from_json(json)
+    }
+
+    // NOTE: The recordings are long, keep them compact.
+    pub fn to_json(&self) -> Result<String, JsonError> {
+        json::to_json(self, false)
+    }
+
+    /// Return the input events of every frame.
+    pub fn frames(&self) -> &[Vec<InputEvent>] {
+        &self.frames
+    }
+
+    /// Return the number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecorderState {
+    Idle,
+    Recording,
+    Playing,
+}
+
+/// Record the input events of every frame, or play an `InputRecording` back instead of the
+/// events from the event loop.
+///
+/// Paired with `FixedTime`, it makes bug reports and gameplay tests reproducible.
+///
+/// NOTE: All buttons are released when recording or playing starts, so both begin with the same
+/// `Input` state.
+#[derive(Debug)]
+pub struct InputRecorder {
+    state: RecorderState,
+    recording: InputRecording,
+    // the next frame to play.
+    cursor: usize,
+}
+
+impl InputRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: RecorderState::Idle,
+            recording: InputRecording::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Start recording from the next frame, the previous recording is dropped.
+    pub fn record(&mut self) {
+        self.state = RecorderState::Recording;
+        self.recording = InputRecording::new();
+        self.cursor = 0;
+    }
+
+    /// Start playing the recording from the next frame.
+    ///
+    /// The events from the event loop are ignored until the recording finishes.
+    pub fn play(&mut self, recording: InputRecording) {
+        self.state = RecorderState::Playing;
+        self.recording = recording;
+        self.cursor = 0;
+    }
+
+    /// Stop recording or playing, return the recording.
+    pub fn stop(&mut self) -> InputRecording {
+        self.state = RecorderState::Idle;
+        self.cursor = 0;
+
+        mem::take(&mut self.recording)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.state == RecorderState::Recording
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state == RecorderState::Playing
+    }
+
+    /// Return the recording which is being recorded or played.
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+
+    /// Record the events of this frame, or replace them with the recorded ones when playing.
+    pub(crate) fn process(&mut self, evts: &mut Vec<InputEvent>) {
+        match self.state {
+            RecorderState::Idle => {}
+            RecorderState::Recording => {
+                if self.recording.is_empty() {
+                    evts.insert(0, InputEvent::ReleaseAll);
+                }
+
+                self.recording.frames.push(evts.clone());
+            }
+            RecorderState::Playing => match self.recording.frames.get(self.cursor) {
+                Some(frame) => {
+                    evts.clear();
+
+                    if self.cursor == 0 {
+                        evts.push(InputEvent::ReleaseAll);
+                    }
+
+                    evts.extend_from_slice(frame);
+                    self.cursor += 1;
+                }
+                // NOTE: The recording finished, give the control back to the event loop.
+                None => self.state = RecorderState::Idle,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Input, KeyCode, MouseButton};
+    use super::*;
+    use crate::misc::coordinates::Transformation;
+
+    #[test]
+    fn test_input_recorder() {
+        let trf = Transformation::default();
+        let mut recorder = InputRecorder::new();
+        let mut input = Input::new();
+
+        recorder.record();
+
+        let frames = [
+            vec![InputEvent::Key {
+                keycode: KeyCode::Space,
+                pressed: true,
+            }],
+            vec![InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            }],
+            vec![InputEvent::Key {
+                keycode: KeyCode::Space,
+                pressed: false,
+            }],
+        ];
+
+        for frame in frames.iter() {
+            let mut evts = frame.clone();
+            recorder.process(&mut evts);
+            input.apply(&evts, &trf);
+        }
+
+        let recording = recorder.stop();
+        assert!(!recorder.is_recording());
+        assert_eq!(recording.len(), 3);
+
+        let recording = InputRecording::from_json(&recording.to_json().unwrap()).unwrap();
+
+        // NOTE: Hold a key which isn't in the recording, playing must release it.
+        let mut input = Input::new();
+        input.apply(
+            &[InputEvent::Key {
+                keycode: KeyCode::A,
+                pressed: true,
+            }],
+            &trf,
+        );
+
+        recorder.play(recording);
+
+        let mut states = Vec::new();
+        for _ in 0..4 {
+            // The live events are ignored while playing.
+            let mut evts = vec![InputEvent::Key {
+                keycode: KeyCode::B,
+                pressed: true,
+            }];
+            recorder.process(&mut evts);
+            input.apply(&evts, &trf);
+
+            states.push((
+                input.keyboard.just_pressed(KeyCode::Space),
+                input.mouse.pressed(MouseButton::Left),
+                input.keyboard.just_released(KeyCode::Space),
+            ));
+
+            assert!(input.keyboard.released(KeyCode::A));
+        }
+
+        assert_eq!(
+            &states[..3],
+            &[
+                (true, false, false),
+                (false, false, false),
+                (false, true, true)
+            ]
+        );
+        // The recording finished, the live events come back.
+        assert!(!recorder.is_playing());
+        assert!(input.keyboard.just_pressed(KeyCode::B));
+    }
+}
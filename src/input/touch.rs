@@ -0,0 +1,424 @@
+use super::TouchPhase;
+
+use crate::{misc::coordinates::Transformation, nalgebra::Vector4};
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// The distance(in screen space) a finger can move and still be treated as a tap.
+pub const TAP_SLOP: f32 = 16.0;
+
+/// The longest time a finger can stay on the screen and still be treated as a tap.
+pub const TAP_MAX_DURATION: Duration = Duration::from_millis(500);
+
+/// The gestures recognized from touches in this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// A finger touched and left quickly without moving, the position is in screen space.
+    Tap { position: (f32, f32) },
+    /// A single finger moved, the delta is in screen space.
+    Drag { delta: (f32, f32) },
+    /// Two fingers moved closer or apart, the scale is the ratio of the distance between them
+    /// in this frame to the last frame, the center is in screen space.
+    Pinch { scale: f32, center: (f32, f32) },
+    /// Two fingers rotated around each other, the angle is in radians and counterclockwise on
+    /// screen is positive.
+    Rotate { angle: f32 },
+}
+
+/// A finger on the touch screen.
+#[derive(Debug, Clone, Copy)]
+pub struct Touch {
+    id: u64,
+    phase: TouchPhase,
+    // positions in `screen space`.
+    start_position_ss: (f32, f32),
+    last_position_ss: (f32, f32),
+    position_ss: (f32, f32),
+    start_time: Instant,
+    // the finger moved out of `TAP_SLOP`, stayed longer than `TAP_MAX_DURATION`, or touched with
+    // other fingers.
+    tap_canceled: bool,
+
+    trf: Transformation,
+}
+
+impl Touch {
+    /// The unique id of the finger.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Return the last phase of the finger in this frame.
+    pub fn phase(&self) -> TouchPhase {
+        self.phase
+    }
+
+    /// Detect whether the finger has just touched the screen.
+    pub fn just_started(&self) -> bool {
+        self.phase == TouchPhase::Started
+    }
+
+    /// Detect whether the finger has just left the screen, or the touch is cancelled.
+    pub fn just_ended(&self) -> bool {
+        self.phase == TouchPhase::Ended || self.phase == TouchPhase::Cancelled
+    }
+
+    /// Return how long the finger has been on the screen.
+    pub fn duration(&self) -> Duration {
+        self.start_time.elapsed()
+    }
+
+    /// Return the position where the finger started touching(in screen space).
+    pub fn start_position_in_ss(&self) -> (f32, f32) {
+        self.start_position_ss
+    }
+
+    /// Return the position of the finger in `screen space`.
+    pub fn position_in_ss(&self) -> (f32, f32) {
+        self.position_ss
+    }
+
+    /// Return the position of the finger in `view space`.
+    pub fn position_in_vs(&self) -> (f32, f32) {
+        let p_vs =
+            self.trf.mx_s2v() * Vector4::new(self.position_ss.0, self.position_ss.1, 0.0, 1.0);
+
+        (p_vs.x, p_vs.y)
+    }
+
+    /// Return the position of the finger in `world space`.
+    pub fn position_in_ws(&self) -> (f32, f32) {
+        let p_ws =
+            self.trf.mx_s2w() * Vector4::new(self.position_ss.0, self.position_ss.1, 0.0, 1.0);
+
+        (p_ws.x, p_ws.y)
+    }
+
+    /// Return the difference in the position of the finger between two frames(in screen space).
+    pub fn motion_in_ss(&self) -> (f32, f32) {
+        (
+            self.position_ss.0 - self.last_position_ss.0,
+            self.position_ss.1 - self.last_position_ss.1,
+        )
+    }
+
+    /// Return the difference in the position of the finger between two frames(in world space).
+    pub fn motion_in_ws(&self) -> (f32, f32) {
+        let (dx, dy) = self.motion_in_ss();
+        let m_ws = self.trf.mx_s2w() * Vector4::new(dx, dy, 0.0, 0.0);
+
+        (m_ws.x, m_ws.y)
+    }
+}
+
+/// All fingers on the touch screen, and the gestures recognized from them.
+///
+/// NOTE: The gestures except `Gesture::Tap` are recognized by positions only, so they are
+/// reproducible when playing an `InputRecording`. The tap also depends on the wall-clock time
+/// the finger stays, see `TAP_MAX_DURATION`.
+pub struct Touches {
+    touches: HashMap<u64, Touch>,
+    gestures: Vec<Gesture>,
+    // the fingers cancelled by `Self::cancel_all()` but still on the screen.
+    canceled: HashSet<u64>,
+}
+
+impl Touches {
+    pub(super) fn new() -> Self {
+        Self {
+            touches: HashMap::with_capacity(4),
+            gestures: Vec::with_capacity(4),
+            canceled: HashSet::with_capacity(4),
+        }
+    }
+
+    /// Return the finger by id, the fingers left the screen are kept for one frame.
+    pub fn get(&self, id: u64) -> Option<&Touch> {
+        self.touches.get(&id)
+    }
+
+    /// Return all fingers, including the ones just left the screen.
+    pub fn iter(&self) -> impl Iterator<Item = &Touch> {
+        self.touches.values()
+    }
+
+    /// Return the number of fingers, including the ones just left the screen.
+    pub fn len(&self) -> usize {
+        self.touches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.touches.is_empty()
+    }
+
+    /// Return the gestures recognized in this frame.
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Return the position of the tap in this frame(in screen space).
+    pub fn tap(&self) -> Option<(f32, f32)> {
+        self.gestures.iter().find_map(|gesture| match *gesture {
+            Gesture::Tap { position } => Some(position),
+            _ => None,
+        })
+    }
+
+    /// Return the delta of the drag in this frame(in screen space).
+    pub fn drag(&self) -> Option<(f32, f32)> {
+        self.gestures.iter().find_map(|gesture| match *gesture {
+            Gesture::Drag { delta } => Some(delta),
+            _ => None,
+        })
+    }
+
+    /// Return the scale of the pinch in this frame.
+    pub fn pinch(&self) -> Option<f32> {
+        self.gestures.iter().find_map(|gesture| match *gesture {
+            Gesture::Pinch { scale, .. } => Some(scale),
+            _ => None,
+        })
+    }
+
+    /// Return the angle of the rotation in this frame(in radians).
+    pub fn rotation(&self) -> Option<f32> {
+        self.gestures.iter().find_map(|gesture| match *gesture {
+            Gesture::Rotate { angle } => Some(angle),
+            _ => None,
+        })
+    }
+
+    pub(super) fn before_apply(&mut self) {
+        self.gestures.clear();
+        self.touches.retain(|_, touch| !touch.just_ended());
+
+        for touch in self.touches.values_mut() {
+            touch.phase = TouchPhase::Moved;
+            touch.last_position_ss = touch.position_ss;
+        }
+    }
+
+    pub(super) fn touch(&mut self, id: u64, phase: TouchPhase, position_ss: (f32, f32)) {
+        // NOTE: The cancelled finger is ignored until it leaves the screen, or the id is reused
+        // by a new finger.
+        if self.canceled.contains(&id) {
+            match phase {
+                TouchPhase::Started => {
+                    self.canceled.remove(&id);
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    self.canceled.remove(&id);
+                    return;
+                }
+                TouchPhase::Moved => return,
+            }
+        }
+
+        match self.touches.get_mut(&id) {
+            Some(touch) if phase != TouchPhase::Started => {
+                touch.phase = phase;
+                touch.position_ss = position_ss;
+            }
+            // NOTE: The finger is unknown.
+            None if phase == TouchPhase::Ended || phase == TouchPhase::Cancelled => {}
+            _ => {
+                self.touches.insert(
+                    id,
+                    Touch {
+                        id,
+                        phase,
+                        start_position_ss: position_ss,
+                        last_position_ss: position_ss,
+                        position_ss,
+                        start_time: Instant::now(),
+                        tap_canceled: false,
+                        trf: Transformation::default(),
+                    },
+                );
+            }
+        }
+    }
+
+    pub(super) fn after_apply(&mut self, trf: &Transformation) {
+        let multi = self.touches.len() > 1;
+
+        for touch in self.touches.values_mut() {
+            touch.trf = *trf;
+
+            let (dx, dy) = (
+                touch.position_ss.0 - touch.start_position_ss.0,
+                touch.position_ss.1 - touch.start_position_ss.1,
+            );
+            touch.tap_canceled |= multi
+                || dx * dx + dy * dy > TAP_SLOP * TAP_SLOP
+                || touch.duration() > TAP_MAX_DURATION;
+        }
+
+        self.recognize();
+    }
+
+    /// Cancel all fingers, e.g. the app is suspended.
+    pub(super) fn cancel_all(&mut self) {
+        for touch in self.touches.values_mut() {
+            if !touch.just_ended() {
+                self.canceled.insert(touch.id);
+            }
+
+            touch.phase = TouchPhase::Cancelled;
+            touch.tap_canceled = true;
+        }
+    }
+
+    fn recognize(&mut self) {
+        for touch in self.touches.values() {
+            if touch.phase == TouchPhase::Ended && !touch.tap_canceled {
+                self.gestures.push(Gesture::Tap {
+                    position: touch.position_ss,
+                });
+            }
+        }
+
+        let mut actives = self.touches.values().filter(|touch| !touch.just_ended());
+
+        match (actives.next(), actives.next(), actives.next()) {
+            (Some(touch), None, None) => {
+                let delta = touch.motion_in_ss();
+
+                if touch.tap_canceled && delta != (0.0, 0.0) {
+                    self.gestures.push(Gesture::Drag { delta });
+                }
+            }
+            // NOTE: Both fingers must be on the screen in the last frame to compare.
+            (Some(a), Some(b), None) if !a.just_started() && !b.just_started() => {
+                let last = (
+                    b.last_position_ss.0 - a.last_position_ss.0,
+                    b.last_position_ss.1 - a.last_position_ss.1,
+                );
+                let now = (
+                    b.position_ss.0 - a.position_ss.0,
+                    b.position_ss.1 - a.position_ss.1,
+                );
+
+                let last_len = (last.0 * last.0 + last.1 * last.1).sqrt();
+                let now_len = (now.0 * now.0 + now.1 * now.1).sqrt();
+
+                if last_len > 0.0 && now_len > 0.0 {
+                    if now_len != last_len {
+                        self.gestures.push(Gesture::Pinch {
+                            scale: now_len / last_len,
+                            center: (
+                                (a.position_ss.0 + b.position_ss.0) / 2.0,
+                                (a.position_ss.1 + b.position_ss.1) / 2.0,
+                            ),
+                        });
+                    }
+
+                    // NOTE: The y axis of screen space points down, flip it to make
+                    // counterclockwise positive.
+                    let angle = (-now.1).atan2(now.0) - (-last.1).atan2(last.0);
+                    let angle = if angle > std::f32::consts::PI {
+                        angle - 2.0 * std::f32::consts::PI
+                    } else if angle < -std::f32::consts::PI {
+                        angle + 2.0 * std::f32::consts::PI
+                    } else {
+                        angle
+                    };
+
+                    if angle != 0.0 {
+                        self.gestures.push(Gesture::Rotate { angle });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Input, InputEvent};
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> InputEvent {
+        InputEvent::Touch { id, phase, x, y }
+    }
+
+    #[test]
+    fn test_touch_gestures() {
+        let trf = Transformation::default();
+        let mut input = Input::new();
+
+        // tap
+        input.apply(&[touch(0, TouchPhase::Started, 10.0, 10.0)], &trf);
+        assert!(input.touches.get(0).unwrap().just_started());
+        assert!(input.touches.gestures().is_empty());
+
+        input.apply(&[touch(0, TouchPhase::Ended, 12.0, 10.0)], &trf);
+        assert!(input.touches.get(0).unwrap().just_ended());
+        assert_eq!(input.touches.tap(), Some((12.0, 10.0)));
+
+        input.apply(&[], &trf);
+        assert!(input.touches.is_empty());
+
+        // drag
+        input.apply(&[touch(1, TouchPhase::Started, 0.0, 0.0)], &trf);
+        input.apply(&[touch(1, TouchPhase::Moved, 40.0, 0.0)], &trf);
+        assert_eq!(input.touches.drag(), Some((40.0, 0.0)));
+        assert_eq!(input.touches.get(1).unwrap().motion_in_ss(), (40.0, 0.0));
+
+        input.apply(&[touch(1, TouchPhase::Ended, 40.0, 0.0)], &trf);
+        assert_eq!(input.touches.tap(), None);
+
+        // pinch and rotate
+        input.apply(
+            &[
+                touch(2, TouchPhase::Started, 0.0, 0.0),
+                touch(3, TouchPhase::Started, 10.0, 0.0),
+            ],
+            &trf,
+        );
+        assert!(input.touches.gestures().is_empty());
+
+        input.apply(&[touch(3, TouchPhase::Moved, 0.0, -20.0)], &trf);
+        assert_eq!(input.touches.pinch(), Some(2.0));
+        assert!((input.touches.rotation().unwrap() - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(input.touches.drag(), None);
+
+        // suspended
+        input.apply(&[InputEvent::ReleaseAll], &trf);
+        assert!(input.touches.iter().all(|touch| touch.just_ended()));
+
+        // NOTE: The cancelled fingers are ignored after they are removed, until they leave.
+        input.apply(&[touch(2, TouchPhase::Moved, 0.0, 0.0)], &trf);
+        assert!(input.touches.is_empty());
+
+        input.apply(&[touch(2, TouchPhase::Ended, 0.0, 0.0)], &trf);
+        assert_eq!(input.touches.tap(), None);
+
+        input.apply(&[touch(3, TouchPhase::Ended, 0.0, -20.0)], &trf);
+        assert_eq!(input.touches.tap(), None);
+
+        // the id is reused by a new finger.
+        input.apply(&[touch(2, TouchPhase::Started, 5.0, 5.0)], &trf);
+        input.apply(&[touch(2, TouchPhase::Ended, 5.0, 5.0)], &trf);
+        assert_eq!(input.touches.tap(), Some((5.0, 5.0)));
+    }
+
+    #[test]
+    fn test_touch_long_press() {
+        let trf = Transformation::default();
+        let mut input = Input::new();
+
+        input.apply(&[touch(0, TouchPhase::Started, 10.0, 10.0)], &trf);
+
+        // NOTE: Pretend the finger has stayed longer than `TAP_MAX_DURATION`.
+        let touch_0 = input.touches.touches.get_mut(&0).unwrap();
+        touch_0.start_time = Instant::now().checked_sub(TAP_MAX_DURATION * 2).unwrap();
+
+        input.apply(&[touch(0, TouchPhase::Ended, 10.0, 10.0)], &trf);
+        assert_eq!(input.touches.tap(), None);
+    }
+}
@@ -1,8 +1,13 @@
 pub mod app;
+pub mod asset;
 pub mod components;
+pub mod events;
 pub mod input;
 pub mod misc;
+pub mod plugin;
 pub mod render;
+pub mod state;
+pub mod task;
 pub mod window;
 
 // Use crate `legion` as the ecs framework of yam engine.
@@ -15,20 +20,43 @@ pub extern crate legion;
 pub extern crate nalgebra;
 
 pub use app::*;
+pub use asset::{AssetError, AssetPlugin, AssetServer, Handle, Texture};
 pub use components::{
-    camera::Camera2D,
+    camera::{Camera2D, WindowTarget},
     geometry::{
         Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType, Geometry2DType,
         GeometryType, InnerDecoration,
     },
-    sprite::Sprite,
-    time::{DiagnosticTimer, Time},
+    hierarchy::{Children, LocalTransform2D, Parent, TransformPlugin},
+    sprite::{Sprite, UvRect},
+    time::{DiagnosticTimer, FixedTime, Time, MAX_TIME_SCALE},
     transform::Transform2D,
     Instance,
 };
-pub use input::{Input, KeyCode, MouseButton};
-pub use misc::color::{Hex, Rgba};
-pub use window::{Fullscreen, MonitorHandle, VideoMode, Window};
+pub use events::{EventReader, EventWriter, Events};
+#[cfg(feature = "gilrs")]
+pub use input::GilrsBackend;
+pub use input::{
+    ActionMap, Axis2DBinding, AxisBinding, Binding, Gamepad, GamepadAxis, GamepadBackend,
+    GamepadButton, GamepadId, Gamepads, Gesture, Input, InputEvent, InputPlugin, InputRecorder,
+    InputRecording, KeyCode, MockGamepad, MockGamepadBackend, Modifiers, MouseButton, Touch,
+    TouchPhase, Touches, PIXELS_PER_LINE,
+};
+pub use misc::{
+    color::{Hex, Rgba},
+    json::JsonError,
+};
+pub use plugin::Plugin;
+pub use render::{
+    DebugDraw, DebugDrawPlugin, FrameCapture, OffscreenRender, RenderError, RenderPlugin,
+    RenderStats,
+};
+pub use state::{AppState, AppStateBuilder, AppStateError, StateEvent};
+pub use task::{Task, TaskPlugin, TaskPool};
+pub use window::{
+    CursorIcon, Fullscreen, IconError, MonitorHandle, PresentMode, VideoMode, Window,
+    WindowDescriptor, WindowId, WindowMode, WindowPlugin, WindowResized, Windows,
+};
 
 const DEFAULT_WIDTH: u32 = 1280;
 const DEFAULT_HEIGHT: u32 = 720;
@@ -0,0 +1,90 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The errors may occur when loading or saving the json files of `yam`, like `ActionMap`.
+#[derive(Debug)]
+pub enum JsonError {
+    Io { path: PathBuf, error: io::Error },
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Io { path, error } => {
+                write!(f, "failed to access '{}': {}", path.display(), error)
+            }
+            JsonError::Parse(error) => write!(f, "failed to parse json: {}", error),
+        }
+    }
+}
+
+impl Error for JsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JsonError::Io { error, .. } => Some(error),
+            JsonError::Parse(error) => Some(error),
+        }
+    }
+}
+
+/// Read and deserialize the json file.
+pub(crate) fn load<T: DeserializeOwned>(path: &Path) -> Result<T, JsonError> {
+    let json = fs::read_to_string(path).map_err(|error| JsonError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    from_json(&json)
+}
+
+/// Write the json to the file.
+pub(crate) fn save(path: &Path, json: &str) -> Result<(), JsonError> {
+    fs::write(path, json).map_err(|error| JsonError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+pub(crate) fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, JsonError> {
+    serde_json::from_str(json).map_err(JsonError::Parse)
+}
+
+/// Serialize the value, `pretty` for the files edited by hand.
+pub(crate) fn to_json<T: Serialize>(value: &T, pretty: bool) -> Result<String, JsonError> {
+    if pretty {
+        serde_json::to_string_pretty(value).map_err(JsonError::Parse)
+    } else {
+        serde_json::to_string(value).map_err(JsonError::Parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_file() {
+        let path = std::env::temp_dir().join("yam_test_json_file.json");
+
+        save(&path, &to_json(&vec![1, 2, 3], true).unwrap()).unwrap();
+        let loaded: Vec<u32> = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, [1, 2, 3]);
+
+        match load::<Vec<u32>>(&path) {
+            Err(JsonError::Io {
+                path: error_path, ..
+            }) => assert_eq!(error_path, path),
+            other => panic!("ERR: Expect io error, but get {:?}", other),
+        }
+
+        assert!(matches!(from_json::<u32>("[]"), Err(JsonError::Parse(_))));
+        assert_eq!(to_json(&[1, 2], false).unwrap(), "[1,2]");
+    }
+}
@@ -1,3 +1,4 @@
 pub mod color;
 pub mod coordinates;
+pub mod json;
 pub mod viewport;
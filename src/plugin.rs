@@ -0,0 +1,244 @@
+use crate::{
+    app::{AppBuildError, AppBuilder, AppStage},
+    legion::Resources,
+};
+
+use std::fmt;
+
+/// A feature packaged with its stages, states, events and resources, like physics or a camera
+/// controller, which is added by `AppBuilder::add_plugin()`.
+///
+/// ```
+/// use yam::*;
+///
+/// struct Score(u32);
+///
+/// struct ScorePlugin;
+///
+/// impl Plugin for ScorePlugin {
+///     fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+///         app.insert_resource(Score(0))
+///             .insert_stage(AppStageBuilder::new(String::from("score")))?;
+///
+///         Ok(())
+///     }
+/// }
+///
+/// let app = AppBuilder::new().add_plugin(ScorePlugin).unwrap().build().unwrap();
+/// ```
+pub trait Plugin: 'static {
+    /// The unique name of the plugin, other plugins depend on it by the name.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// The names of the plugins which must be built before this plugin.
+    fn dependencies(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Add the stages, states, events and resources of the plugin.
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError>;
+}
+
+/// Insert a resource declared by `AppBuilder::insert_resource()` when the `App` runs.
+pub(crate) struct ResourceInit(Box<dyn FnOnce(&mut Resources)>);
+
+impl ResourceInit {
+    pub(crate) fn new<T: 'static>(resource: T) -> Self {
+        Self(Box::new(move |resources| resources.insert(resource)))
+    }
+
+    pub(crate) fn with<T: 'static, F: FnOnce(&Resources) -> T + 'static>(init: F) -> Self {
+        Self(Box::new(move |resources| {
+            let resource = init(resources);
+            resources.insert(resource);
+        }))
+    }
+
+    pub(crate) fn insert(self, resources: &mut Resources) {
+        (self.0)(resources);
+    }
+}
+
+impl fmt::Debug for ResourceInit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceInit").finish()
+    }
+}
+
+type CreateSystemStage = dyn FnOnce(&Resources) -> Option<AppStage>;
+
+/// Create a system stage declared by `AppBuilder::insert_system_stage()` when the `App` runs,
+/// `None` if it can't run, e.g. `default_render` without a window.
+pub(crate) struct SystemStageInit(Box<CreateSystemStage>);
+
+impl SystemStageInit {
+    pub(crate) fn new<F: FnOnce(&Resources) -> Option<AppStage> + 'static>(init: F) -> Self {
+        Self(Box::new(init))
+    }
+
+    pub(crate) fn create(self, resources: &Resources) -> Option<AppStage> {
+        (self.0)(resources).map(AppStage::into_system)
+    }
+}
+
+impl fmt::Debug for SystemStageInit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SystemStageInit").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{AppSettings, AppStageBuilder, HeadlessMode},
+        components::hierarchy::TransformPlugin,
+        events::Events,
+        input::{Input, InputEvent, InputPlugin},
+        render::DebugDraw,
+        task::TaskPool,
+        window::Windows,
+    };
+
+    #[derive(Default)]
+    struct Built(Vec<&'static str>);
+
+    struct TestPlugin {
+        name: &'static str,
+        dependencies: Vec<&'static str>,
+    }
+
+    impl TestPlugin {
+        fn new(name: &'static str, dependencies: &[&'static str]) -> Self {
+            Self {
+                name,
+                dependencies: dependencies.to_vec(),
+            }
+        }
+    }
+
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn dependencies(&self) -> Vec<&str> {
+            self.dependencies.clone()
+        }
+
+        fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+            let name = self.name;
+
+            // NOTE: The plugin "b" adds the plugin "c", which depends on "a".
+            if name == "b" {
+                app.insert_plugin(TestPlugin::new("c", &["a"]))?;
+            }
+
+            app.insert_stage(
+                AppStageBuilder::new(String::from(name)).add_thread_local_fn_startup(
+                    move |_, resources| resources.get_mut_or_default::<Built>().0.push(name),
+                ),
+            )?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plugins() {
+        AppBuilder::new()
+            .add_plugin(TestPlugin::new("b", &["a"]))
+            .unwrap()
+            .add_plugin(TestPlugin::new("a", &[]))
+            .unwrap()
+            .add_plugin(TestPlugin::new("d", &[]))
+            .unwrap()
+            .disable_plugin("d")
+            .add_resource(Built::default())
+            .create_stage_builder(String::from("check"))
+            .unwrap()
+            // NOTE: The stages of plugins are added after the stages of user.
+            .add_thread_local_fn_process(|_, resources| {
+                assert_eq!(resources.get::<Built>().unwrap().0, ["a", "b", "c"]);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(1));
+
+        assert!(matches!(
+            AppBuilder::new()
+                .add_plugin(TestPlugin::new("a", &[]))
+                .unwrap()
+                .add_plugin(TestPlugin::new("a", &[])),
+            Err(AppBuildError::DuplicatePlugin(_))
+        ));
+
+        assert!(matches!(
+            AppBuilder::new()
+                .add_plugin(TestPlugin::new("b", &["a"]))
+                .unwrap()
+                .add_plugin(TestPlugin::new("a", &[]))
+                .unwrap()
+                .disable_plugin("a")
+                .build(),
+            Err(AppBuildError::PluginNotExist { .. })
+        ));
+
+        assert!(matches!(
+            AppBuilder::new()
+                .add_plugin(TestPlugin::new("x", &["y"]))
+                .unwrap()
+                .add_plugin(TestPlugin::new("y", &["x"]))
+                .unwrap()
+                .build(),
+            Err(AppBuildError::CyclicPlugins(_))
+        ));
+    }
+
+    #[test]
+    fn test_default_plugins() {
+        AppBuilder::new()
+            .create_stage_builder(String::from("check"))
+            .unwrap()
+            .add_thread_local_fn_startup(|_, resources| {
+                assert!(resources.contains::<Input>());
+                assert!(resources.contains::<Events<InputEvent>>());
+                assert!(resources.contains::<DebugDraw>());
+                assert!(resources.contains::<Windows>());
+                assert!(resources.contains::<TaskPool>());
+
+                let settings = resources.get::<AppSettings>().unwrap();
+                let names: Vec<String> = settings
+                    .busy_stages()
+                    .iter()
+                    .map(|stage| String::from(stage.name()))
+                    .collect();
+                // NOTE: `default_render` isn't created without a window.
+                assert_eq!(names, ["check", "default_transform"]);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(1));
+
+        AppBuilder::new()
+            .disable_plugin_of::<InputPlugin>()
+            .disable_plugin_of::<TransformPlugin>()
+            .create_stage_builder(String::from("check"))
+            .unwrap()
+            .add_thread_local_fn_process(|_, resources| {
+                assert!(!resources.contains::<Input>());
+                assert!(!resources.contains::<Events<InputEvent>>());
+
+                let settings = resources.get::<AppSettings>().unwrap();
+                assert!(!settings.is_in_busy("default_transform"));
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(2));
+    }
+}
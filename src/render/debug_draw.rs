@@ -0,0 +1,304 @@
+use crate::{
+    app::{AppBuildError, AppBuilder},
+    components::geometry::{
+        BorderDecoration, BorderThickness, Geometry, Geometry1DType, Geometry2DType,
+        InnerDecoration,
+    },
+    misc::color::Rgba,
+    nalgebra::Vector2,
+    plugin::Plugin,
+};
+
+/// Draw on top of the other geometries.
+const DEBUG_ORDER: u8 = 255;
+
+/// Immediate-mode drawing of lines and shapes in `world space`, useful for grids, aim lines
+/// and debugging.
+///
+/// The shapes are drawn by the `default_render` stage in the frame they are added, then cleared.
+///
+/// ```no_run
+/// use yam::{legion::system, nalgebra::Vector2, BorderDecoration, DebugDraw, Rgba};
+///
+/// #[system]
+/// fn draw_aim_line(#[resource] debug_draw: &mut DebugDraw) {
+///     debug_draw.segment(
+///         Vector2::new(0.0, 0.0),
+///         Vector2::new(64.0, 64.0),
+///         BorderDecoration::Dash,
+///         Rgba::RED,
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DebugDraw {
+    thickness: BorderThickness,
+    geometries: Vec<Geometry>,
+}
+
+impl DebugDraw {
+    /// The default thickness of lines, 2 pixels in `screen space`.
+    pub const DEFAULT_THICKNESS: BorderThickness = BorderThickness::ScreenSpace(2.0);
+
+    pub fn new() -> Self {
+        Self {
+            thickness: Self::DEFAULT_THICKNESS,
+            geometries: Vec::new(),
+        }
+    }
+
+    /// The thickness of lines drawn after.
+    pub fn thickness(&self) -> BorderThickness {
+        self.thickness
+    }
+
+    pub fn set_thickness(&mut self, thickness: BorderThickness) {
+        self.thickness = thickness;
+    }
+
+    /// Draw an infinite line through `a` and `b`.
+    pub fn line(&mut self, a: Vector2<f32>, b: Vector2<f32>, bdeco: BorderDecoration, color: Rgba) {
+        self.push_1d(Geometry1DType::Line, a, b, bdeco, color);
+    }
+
+    /// Draw a segment from `a` to `b`.
+    pub fn segment(
+        &mut self,
+        a: Vector2<f32>,
+        b: Vector2<f32>,
+        bdeco: BorderDecoration,
+        color: Rgba,
+    ) {
+        self.push_1d(Geometry1DType::Segment, a, b, bdeco, color);
+    }
+
+    /// Draw the border of a circle.
+    pub fn circle(
+        &mut self,
+        centre: Vector2<f32>,
+        radius: f32,
+        bdeco: BorderDecoration,
+        color: Rgba,
+    ) {
+        self.geometries.push(Geometry::new_2d(
+            Geometry2DType::Circle,
+            bdeco,
+            color,
+            self.thickness,
+            InnerDecoration::None,
+            Rgba::default(),
+            DEBUG_ORDER,
+            centre,
+            0.0,
+            2.0 * radius,
+        ));
+    }
+
+    /// Draw the border of a closed polygon through `points`.
+    pub fn polygon(&mut self, points: &[Vector2<f32>], bdeco: BorderDecoration, color: Rgba) {
+        if points.len() < 2 {
+            return;
+        }
+
+        for (i, a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            self.segment(*a, b, bdeco, color);
+        }
+    }
+
+    /// Draw a cross at `position` with `text` on the right of it.
+    ///
+    /// `size` is the height of the text in `world space`. Only ASCII letters, digits and
+    /// a few punctuations can be shown, the other characters are shown as `?`.
+    pub fn text_marker(&mut self, position: Vector2<f32>, text: &str, size: f32, color: Rgba) {
+        let half = size / 4.0;
+
+        self.segment(
+            position - Vector2::new(half, half),
+            position + Vector2::new(half, half),
+            BorderDecoration::Solid,
+            color,
+        );
+        self.segment(
+            position - Vector2::new(half, -half),
+            position + Vector2::new(half, -half),
+            BorderDecoration::Solid,
+            color,
+        );
+
+        // A glyph is 2 units wide, 4 units high, and advances 3 units.
+        let unit = size / 4.0;
+        let mut origin = position + Vector2::new(2.0 * half + unit, -size / 2.0);
+
+        for c in text.chars() {
+            for [x0, y0, x1, y1] in glyph(c).iter() {
+                let a = origin + Vector2::new(*x0 as f32, *y0 as f32) * unit;
+                let b = origin + Vector2::new(*x1 as f32, *y1 as f32) * unit;
+
+                self.segment(a, b, BorderDecoration::Solid, color);
+            }
+
+            origin.x += 3.0 * unit;
+        }
+    }
+
+    /// Remove all shapes.
+    pub fn clear(&mut self) {
+        self.geometries.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.geometries.is_empty()
+    }
+
+    /// The geometries in `world space` to draw in this frame.
+    pub(crate) fn geometries(&self) -> &[Geometry] {
+        &self.geometries
+    }
+
+    fn push_1d(
+        &mut self,
+        gtype: Geometry1DType,
+        a: Vector2<f32>,
+        b: Vector2<f32>,
+        bdeco: BorderDecoration,
+        color: Rgba,
+    ) {
+        // NOTE: A zero-length line has no direction, skip it.
+        if a == b {
+            return;
+        }
+
+        self.geometries.push(Geometry::new_1d(
+            gtype,
+            bdeco,
+            color,
+            self.thickness,
+            DEBUG_ORDER,
+            a,
+            b,
+        ));
+    }
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The strokes of a glyph in a 2x4 grid, `[x0, y0, x1, y1]`, origin at the left-bottom.
+#[rustfmt::skip]
+fn glyph(c: char) -> &'static [[u8; 4]] {
+    match c.to_ascii_uppercase() {
+        ' ' => &[],
+        '0' | 'O' => &[[0, 0, 2, 0], [2, 0, 2, 4], [2, 4, 0, 4], [0, 4, 0, 0]],
+        '1' => &[[1, 0, 1, 4], [0, 3, 1, 4], [0, 0, 2, 0]],
+        '2' => &[[0, 4, 2, 4], [2, 4, 2, 2], [2, 2, 0, 2], [0, 2, 0, 0], [0, 0, 2, 0]],
+        '3' => &[[0, 4, 2, 4], [2, 4, 2, 0], [2, 0, 0, 0], [0, 2, 2, 2]],
+        '4' => &[[0, 4, 0, 2], [0, 2, 2, 2], [2, 4, 2, 0]],
+        '5' | 'S' => &[[2, 4, 0, 4], [0, 4, 0, 2], [0, 2, 2, 2], [2, 2, 2, 0], [2, 0, 0, 0]],
+        '6' => &[[2, 4, 0, 4], [0, 4, 0, 0], [0, 0, 2, 0], [2, 0, 2, 2], [2, 2, 0, 2]],
+        '7' => &[[0, 4, 2, 4], [2, 4, 1, 0]],
+        '8' => &[[0, 0, 2, 0], [2, 0, 2, 4], [2, 4, 0, 4], [0, 4, 0, 0], [0, 2, 2, 2]],
+        '9' => &[[2, 2, 0, 2], [0, 2, 0, 4], [0, 4, 2, 4], [2, 4, 2, 0], [2, 0, 0, 0]],
+        'A' => &[[0, 0, 0, 3], [0, 3, 1, 4], [1, 4, 2, 3], [2, 3, 2, 0], [0, 2, 2, 2]],
+        'B' => &[[0, 0, 0, 4], [0, 4, 1, 4], [1, 4, 2, 3], [2, 3, 1, 2], [0, 2, 1, 2], [1, 2, 2, 1], [2, 1, 1, 0], [1, 0, 0, 0]],
+        'C' => &[[2, 4, 0, 4], [0, 4, 0, 0], [0, 0, 2, 0]],
+        'D' => &[[0, 0, 0, 4], [0, 4, 1, 4], [1, 4, 2, 3], [2, 3, 2, 1], [2, 1, 1, 0], [1, 0, 0, 0]],
+        'E' => &[[2, 4, 0, 4], [0, 4, 0, 0], [0, 0, 2, 0], [0, 2, 1, 2]],
+        'F' => &[[2, 4, 0, 4], [0, 4, 0, 0], [0, 2, 1, 2]],
+        'G' => &[[2, 4, 0, 4], [0, 4, 0, 0], [0, 0, 2, 0], [2, 0, 2, 2], [2, 2, 1, 2]],
+        'H' => &[[0, 0, 0, 4], [2, 0, 2, 4], [0, 2, 2, 2]],
+        'I' => &[[0, 4, 2, 4], [1, 4, 1, 0], [0, 0, 2, 0]],
+        'J' => &[[2, 4, 2, 0], [2, 0, 0, 0], [0, 0, 0, 1]],
+        'K' => &[[0, 0, 0, 4], [0, 2, 2, 4], [0, 2, 2, 0]],
+        'L' => &[[0, 4, 0, 0], [0, 0, 2, 0]],
+        'M' => &[[0, 0, 0, 4], [0, 4, 1, 2], [1, 2, 2, 4], [2, 4, 2, 0]],
+        'N' => &[[0, 0, 0, 4], [0, 4, 2, 0], [2, 0, 2, 4]],
+        'P' => &[[0, 0, 0, 4], [0, 4, 2, 4], [2, 4, 2, 2], [2, 2, 0, 2]],
+        'Q' => &[[0, 0, 2, 0], [2, 0, 2, 4], [2, 4, 0, 4], [0, 4, 0, 0], [1, 1, 2, 0]],
+        'R' => &[[0, 0, 0, 4], [0, 4, 2, 4], [2, 4, 2, 2], [2, 2, 0, 2], [0, 2, 2, 0]],
+        'T' => &[[0, 4, 2, 4], [1, 4, 1, 0]],
+        'U' => &[[0, 4, 0, 0], [0, 0, 2, 0], [2, 0, 2, 4]],
+        'V' => &[[0, 4, 1, 0], [1, 0, 2, 4]],
+        'W' => &[[0, 4, 0, 0], [0, 0, 1, 2], [1, 2, 2, 0], [2, 0, 2, 4]],
+        'X' => &[[0, 0, 2, 4], [0, 4, 2, 0]],
+        'Y' => &[[0, 4, 1, 2], [2, 4, 1, 2], [1, 2, 1, 0]],
+        'Z' => &[[0, 4, 2, 4], [2, 4, 0, 0], [0, 0, 2, 0]],
+        '-' => &[[0, 2, 2, 2]],
+        '+' => &[[0, 2, 2, 2], [1, 1, 1, 3]],
+        '=' => &[[0, 1, 2, 1], [0, 3, 2, 3]],
+        '_' => &[[0, 0, 2, 0]],
+        '/' => &[[0, 0, 2, 4]],
+        '.' => &[[1, 0, 1, 1]],
+        ',' => &[[1, 1, 0, 0]],
+        ':' => &[[1, 0, 1, 1], [1, 3, 1, 4]],
+        '(' => &[[1, 4, 0, 3], [0, 3, 0, 1], [0, 1, 1, 0]],
+        ')' => &[[1, 4, 2, 3], [2, 3, 2, 1], [2, 1, 1, 0]],
+        _ => &[[0, 3, 1, 4], [1, 4, 2, 3], [2, 3, 1, 2], [1, 2, 1, 1]],
+    }
+}
+
+/// The default plugin of `DebugDraw`.
+pub struct DebugDrawPlugin;
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_resource(DebugDraw::new());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_draw() {
+        let mut debug_draw = DebugDraw::new();
+
+        debug_draw.line(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            BorderDecoration::Solid,
+            Rgba::RED,
+        );
+        // Zero-length, skipped.
+        debug_draw.segment(
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            BorderDecoration::Solid,
+            Rgba::RED,
+        );
+        debug_draw.circle(
+            Vector2::new(0.0, 0.0),
+            8.0,
+            BorderDecoration::Dash,
+            Rgba::RED,
+        );
+        debug_draw.polygon(
+            &[
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(0.0, 1.0),
+            ],
+            BorderDecoration::Solid,
+            Rgba::RED,
+        );
+
+        assert_eq!(debug_draw.geometries().len(), 5);
+        assert_eq!(debug_draw.geometries()[1].size_uncheck(), 16.0);
+
+        // 2 strokes of cross, 3 strokes of `H`, 3 strokes of `I`.
+        debug_draw.clear();
+        debug_draw.text_marker(Vector2::new(0.0, 0.0), "hi", 8.0, Rgba::RED);
+
+        assert_eq!(debug_draw.geometries().len(), 8);
+        assert!(debug_draw
+            .geometries()
+            .iter()
+            .all(|g| g.order() == DEBUG_ORDER));
+    }
+}
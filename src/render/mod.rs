@@ -1,16 +1,29 @@
+mod debug_draw;
 mod renderers;
 
-use renderers::{background_renderer::BackgroundRenderer, geometry_renderer::GeometryRenderer};
+pub use debug_draw::{DebugDraw, DebugDrawPlugin};
+
+use renderers::{
+    background_renderer::BackgroundRenderer, geometry_renderer::GeometryRenderer,
+    sprite_renderer::SpriteRenderer,
+};
 
 use crate::{
-    app::{AppStage, AppStageBuilder},
-    components::{camera::Camera2D, time::Time, transform::Transform2D},
+    app::{AppBuildError, AppBuilder, AppStage, AppStageBuilder},
+    components::{
+        camera::{Camera2D, WindowTarget},
+        time::Time,
+        transform::Transform2D,
+    },
     legion::{IntoQuery, Resources, World},
-    misc::{coordinates::Transformation, viewport::Viewport},
+    misc::{color::Rgba, coordinates::Transformation, viewport::Viewport},
     nalgebra::{Matrix4, Vector2},
-    window::Window,
+    plugin::Plugin,
+    window::{Window, WindowId, Windows},
 };
 
+use std::{collections::HashMap, convert::TryFrom, fmt, mem, path::Path};
+
 // Quad vertex in world coordinate.
 #[cfg_attr(rustfmt, rustfmt_skip)]
 const QUAD_VERTEX: [f32; 16] = [
@@ -30,23 +43,27 @@ const QUAD_INDEX: [u16; 6] = [
 #[rustfmt::skip] const THOUSAND:    usize = 1 << 10;
 #[rustfmt::skip] const MILLION:     usize = THOUSAND * THOUSAND;
 #[rustfmt::skip] const KB:          u64 = 1 << 10;
-#[rustfmt::skip] const MB:          u64 = KB * KB;
 
-pub(crate) fn create_app_stage_render(window: &Window) -> AppStage {
-    let mut r2d = Render2D::new(window);
-    let mut bg_rder = BackgroundRenderer::new(&r2d);
-    let mut g2d_rder = GeometryRenderer::new(&r2d);
+/// The maximum size of a storage buffer binding or a vertex buffer, the renderers never grow
+/// their buffers beyond it.
+///
+/// NOTE: `wgpu::Limits` doesn't report `max_storage_buffer_binding_size` yet, so use the default
+/// limit of WebGPU which every device supports.
+const MAX_BINDING_SIZE: u64 = 128 * KB * KB;
 
-    let render_process = move |world: &mut World, resources: &mut Resources| {
-        let trf = r2d.process(world, resources);
-        resources.insert(trf);
-
-        r2d.begin_draw();
+/// Round `size` up to the multiple of `alignment`.
+fn align_to(size: u64, alignment: u64) -> u64 {
+    match size % alignment {
+        0 => size,
+        rest => size + alignment - rest,
+    }
+}
 
-        bg_rder.render(&r2d, world, resources);
-        g2d_rder.render(&r2d, world, resources);
+pub(crate) fn create_app_stage_render(window: &Window) -> AppStage {
+    let mut renderers = Renderers::new(Render2D::new(window));
 
-        r2d.finish_draw();
+    let render_process = move |world: &mut World, resources: &mut Resources| {
+        renderers.render(world, resources);
     };
 
     AppStageBuilder::new(String::from("default_render"))
@@ -54,19 +71,312 @@ pub(crate) fn create_app_stage_render(window: &Window) -> AppStage {
         .build()
 }
 
+/// The default plugin of the system stage `default_render`, which draws into the `Window`.
+///
+/// NOTE: There's no `Window` in `App::run_headless()`, so the stage isn't created.
+pub struct RenderPlugin;
+
+impl Plugin for RenderPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_system_stage(|resources| {
+            resources
+                .get::<Window>()
+                .map(|window| create_app_stage_render(&window))
+        });
+
+        Ok(())
+    }
+}
+
+/// The errors may occur when creating the render.
+#[derive(Debug)]
+pub enum RenderError {
+    /// There's no suitable GPU adapter(including software adapter) on the system.
+    AdapterNotFound,
+    /// The GPU adapter cannot provide a device.
+    DeviceNotFound(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::AdapterNotFound => write!(f, "no suitable GPU adapter found"),
+            RenderError::DeviceNotFound(err) => write!(f, "no suitable GPU device found: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Render the `World` into a texture instead of the window, then read the frame back to CPU.
+///
+/// It doesn't need a window, so it works with `App::run_headless()` and in tests.
+///
+/// ```no_run
+/// use yam::{legion::*, OffscreenRender};
+///
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+///
+/// let mut render = OffscreenRender::new(1280, 720).unwrap();
+/// render.render(&mut world, &mut resources);
+/// render.capture().save_png("screenshot.png").unwrap();
+/// ```
+pub struct OffscreenRender {
+    renderers: Renderers,
+}
+
+impl OffscreenRender {
+    /// Create a render which draws frames in `width` x `height` pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is zero.
+    pub fn new(width: u32, height: u32) -> Result<Self, RenderError> {
+        assert!(width > 0 && height > 0, "ERR: The frame size is zero.");
+
+        let gpu = futures::executor::block_on(Gpu::new_offscreen(width, height))?;
+
+        Ok(Self {
+            renderers: Renderers::new(Render2D::from_gpu(gpu)),
+        })
+    }
+
+    /// Return the size of the frame.
+    pub fn resolution(&self) -> (u32, u32) {
+        let sc_desc = &self.renderers.r2d.gpu.sc_desc;
+
+        (sc_desc.width, sc_desc.height)
+    }
+
+    /// Modifies the size of the frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is zero.
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        assert!(width > 0 && height > 0, "ERR: The frame size is zero.");
+
+        let present_mode = self.renderers.r2d.gpu.sc_desc.present_mode;
+
+        self.renderers.r2d.reconfigure(width, height, present_mode);
+    }
+
+    /// Draw a frame of the `World` and insert its `RenderStats`, the same as the `default_render`
+    /// stage does.
+    pub fn render(&mut self, world: &mut World, resources: &mut Resources) {
+        self.renderers.render(world, resources);
+    }
+
+    /// Read the last frame back to CPU.
+    pub fn capture(&self) -> FrameCapture {
+        self.renderers.r2d.capture()
+    }
+}
+
+/// A frame read back from GPU, stored as tightly packed RGBA8 rows from top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameCapture {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl FrameCapture {
+    /// Create a `FrameCapture` from RGBA8 datas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `data` is not `width * height * 4`.
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+        assert_eq!(
+            data.len(),
+            (width * height * 4) as usize,
+            "ERR: The length of data does not match the size."
+        );
+
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Create a `FrameCapture` from rows which are padded to `padded_bytes_per_row`.
+    fn from_padded_rows(width: u32, height: u32, padded_bytes_per_row: u32, padded: &[u8]) -> Self {
+        let bytes_per_row = (width * 4) as usize;
+
+        let data = padded
+            .chunks(padded_bytes_per_row as usize)
+            .take(height as usize)
+            .flat_map(|row| &row[..bytes_per_row])
+            .copied()
+            .collect();
+
+        Self::new(width, height, data)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Return the RGBA8 datas.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Return the pixel at (`x`, `y`), the origin is the left-top corner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is out of the frame.
+    pub fn pixel(&self, x: u32, y: u32) -> Rgba {
+        assert!(x < self.width && y < self.height, "ERR: Out of the frame.");
+
+        let index = ((y * self.width + x) * 4) as usize;
+        let px = &self.data[index..index + 4];
+
+        Rgba::new(px[0], px[1], px[2], px[3])
+    }
+
+    /// Save the frame as a PNG file.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> image::ImageResult<()> {
+        image::save_buffer_with_format(
+            path,
+            &self.data,
+            self.width,
+            self.height,
+            image::ColorType::Rgba8,
+            image::ImageFormat::Png,
+        )
+    }
+}
+
+/// The statistics of the last frame, inserted into `Resources` by the render.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    dropped_sprites: usize,
+    dropped_geometries: usize,
+}
+
+impl RenderStats {
+    /// The count of sprites which are not drawn, because they exceed the limit of the
+    /// video card.
+    pub fn dropped_sprites(&self) -> usize {
+        self.dropped_sprites
+    }
+
+    /// The count of geometry instances which are not drawn, because the `Transform2D`s or
+    /// `Geometry`s of their entity exceed the limit of the video card.
+    pub fn dropped_geometries(&self) -> usize {
+        self.dropped_geometries
+    }
+}
+
+/// All renderers of `yam`, draw a frame in order.
+struct Renderers {
+    r2d: Render2D,
+    bg_rder: BackgroundRenderer,
+    s2d_rder: SpriteRenderer,
+    g2d_rder: GeometryRenderer,
+}
+
+impl Renderers {
+    fn new(r2d: Render2D) -> Self {
+        let bg_rder = BackgroundRenderer::new(&r2d);
+        let s2d_rder = SpriteRenderer::new(&r2d);
+        let g2d_rder = GeometryRenderer::new(&r2d);
+
+        Self {
+            r2d,
+            bg_rder,
+            s2d_rder,
+            g2d_rder,
+        }
+    }
+
+    fn render(&mut self, world: &mut World, resources: &mut Resources) {
+        resources.insert(RenderStats::default());
+
+        let trf = self.r2d.process(world, resources, None).unwrap_or_default();
+        resources.insert(trf);
+
+        self.draw(world, resources);
+
+        let ids: Vec<WindowId> = match resources.get::<Windows>() {
+            Some(windows) => {
+                self.r2d.sync_extra_targets(&windows);
+                windows.ids().collect()
+            }
+            None => return,
+        };
+
+        // NOTE: Draw the extra windows by swapping their surfaces into `Render2D`, the extra
+        // window without a camera targeting it isn't drawn.
+        for id in ids {
+            self.r2d.swap_target(id);
+
+            if let Some(trf) = self.r2d.process(world, resources, Some(id)) {
+                resources
+                    .get_mut::<Windows>()
+                    .unwrap()
+                    .set_transformation(id, trf);
+
+                self.draw(world, resources);
+            }
+
+            self.r2d.swap_target(id);
+        }
+    }
+
+    fn draw(&mut self, world: &mut World, resources: &mut Resources) {
+        self.r2d.begin_draw();
+
+        self.bg_rder.render(&self.r2d, world, resources);
+        let dropped_sprites = self.s2d_rder.render(&self.r2d, world, resources);
+        let dropped_geometries = self.g2d_rder.render(&self.r2d, world, resources);
+
+        self.r2d.finish_draw();
+
+        let mut stats = resources.get_mut_or_default::<RenderStats>();
+        stats.dropped_sprites += dropped_sprites;
+        stats.dropped_geometries += dropped_geometries;
+    }
+}
+
+/// Where `Render2D` draws.
+enum RenderTarget {
+    /// Draw into the swap chain of the window.
+    Window {
+        surface: wgpu::Surface,
+        swap_chain: wgpu::SwapChain,
+        frame: Option<wgpu::SwapChainFrame>,
+    },
+    /// Draw into a texture which can be read back to CPU.
+    Offscreen {
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+        is_drawing: bool,
+    },
+}
+
 #[allow(dead_code)]
 struct Gpu {
-    surface: wgpu::Surface,
+    instance: wgpu::Instance,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
-    swap_chain: wgpu::SwapChain,
+    target: RenderTarget,
+    // NOTE: The offscreen target reuses it to record the format and the size of the texture.
     sc_desc: wgpu::SwapChainDescriptor,
-    frame: Option<wgpu::SwapChainFrame>,
 }
 
 impl Gpu {
-    async fn new(window: &winit::window::Window) -> Self {
+    async fn new(window: &winit::window::Window, present_mode: wgpu::PresentMode) -> Self {
         let backend = wgpu::BackendBit::PRIMARY;
         let power_preference = wgpu::PowerPreference::HighPerformance;
 
@@ -81,38 +391,20 @@ impl Gpu {
             .await
             .expect("No suitable GPU adapters found on the system!");
 
+        // NOTE: Only the windowed app reports the adapter, the offscreen one stays quiet.
         let adapter_info = adapter.get_info();
         println!("Using {} ({:?})", adapter_info.name, adapter_info.backend);
 
-        let can_push_constant = !(adapter.features() & wgpu::Features::PUSH_CONSTANTS).is_empty();
-        let max_push_constant_size = adapter.limits().max_push_constant_size;
-
-        if can_push_constant {
+        if adapter.features().contains(wgpu::Features::PUSH_CONSTANTS) {
             println!(
                 "Support PUSH_CONSTANT feature, max push const size: {}.",
-                max_push_constant_size
+                adapter.limits().max_push_constant_size
             );
         } else {
             println!("Not support PUSH_CONSTANT feature.");
         }
 
-        let (features, limits) = if can_push_constant {
-            let mut limits = wgpu::Limits::default();
-            limits.max_push_constant_size = max_push_constant_size;
-            (wgpu::Features::PUSH_CONSTANTS, limits)
-        } else {
-            (wgpu::Features::empty(), wgpu::Limits::default())
-        };
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features,
-                    limits,
-                },
-                None,
-            )
+        let (device, queue) = Self::request_device(&adapter)
             .await
             .expect("Unable to find a suitable GPU adapter!");
 
@@ -123,22 +415,131 @@ impl Gpu {
             height: window.inner_size().height,
             // NOTE: 特别关注这个设置, 跟硬件(显示屏)相关, 不正确的设置可能会导致灵异的bug;
             //  但现在还没碰到相关问题, 先搁置;
-            // NOTE: 先默认设置为Mailbox, 该模式下画面会以垂直刷新率更新, 但与Fifo不同的是,
+            // NOTE: 默认设置为Mailbox, 该模式下画面会以垂直刷新率更新, 但与Fifo不同的是,
             //  GPU一旦绘制完画面, 会立即提交到表现引擎; 而Fifo模式下会通过阻塞线程的方式强制
-            //  帧率与显示器刷新率同步.
-            present_mode: wgpu::PresentMode::Mailbox,
+            //  帧率与显示器刷新率同步. 可以通过`WindowDescriptor`修改, 运行时
+            //  可以通过`Window::set_present_mode()`切换.
+            present_mode,
         };
 
         let swap_chain = device.create_swap_chain(&surface, &sc_desc);
 
         Self {
-            surface,
+            instance,
             adapter,
             device,
             queue,
-            swap_chain,
+            target: RenderTarget::Window {
+                surface,
+                swap_chain,
+                frame: None,
+            },
             sc_desc,
-            frame: None,
+        }
+    }
+
+    async fn new_offscreen(width: u32, height: u32) -> Result<Self, RenderError> {
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+
+        // NOTE: Without a surface, any adapter(including software adapter) is acceptable.
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+            })
+            .await
+            .ok_or(RenderError::AdapterNotFound)?;
+
+        let (device, queue) = Self::request_device(&adapter)
+            .await
+            .map_err(RenderError::DeviceNotFound)?;
+
+        let sc_desc = wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+        };
+
+        let (texture, view) = Self::create_offscreen_texture(&device, &sc_desc);
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            target: RenderTarget::Offscreen {
+                texture,
+                view,
+                is_drawing: false,
+            },
+            sc_desc,
+        })
+    }
+
+    async fn request_device(
+        adapter: &wgpu::Adapter,
+    ) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+        let can_push_constant = !(adapter.features() & wgpu::Features::PUSH_CONSTANTS).is_empty();
+        let max_push_constant_size = adapter.limits().max_push_constant_size;
+
+        let (features, limits) = if can_push_constant {
+            let mut limits = wgpu::Limits::default();
+            limits.max_push_constant_size = max_push_constant_size;
+            (wgpu::Features::PUSH_CONSTANTS, limits)
+        } else {
+            (wgpu::Features::empty(), wgpu::Limits::default())
+        };
+
+        adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features,
+                    limits,
+                },
+                None,
+            )
+            .await
+    }
+
+    fn create_offscreen_texture(
+        device: &wgpu::Device,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen texture"),
+            size: wgpu::Extent3d {
+                width: sc_desc.width,
+                height: sc_desc.height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: sc_desc.format,
+            usage: sc_desc.usage,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    /// Return the view of the frame which is drawing, `None` if drawing has not begun.
+    fn frame_view(&self) -> Option<&wgpu::TextureView> {
+        match &self.target {
+            RenderTarget::Window { frame, .. } => frame.as_ref().map(|frame| &frame.output.view),
+            RenderTarget::Offscreen {
+                view, is_drawing, ..
+            } => {
+                if *is_drawing {
+                    Some(view)
+                } else {
+                    None
+                }
+            }
         }
     }
 }
@@ -152,12 +553,13 @@ struct Render2D {
     quad_index_buf: wgpu::Buffer,
     // Store the common use datas(likes `Time`, `MousePosition`..).
     utility_buf: wgpu::Buffer,
-    // A springboard to transfer data from CPU to GPU.
-    staging_buf: wgpu::Buffer,
     // Depth texture.
     depth_texture: Texture,
 
     viewport: Viewport,
+
+    // The surfaces of the extra windows.
+    extra_targets: HashMap<WindowId, ExtraTarget>,
     // // NOTE: 临时性数据, 用于决定是否更新shader
     // vhash: u64,
     // fhash: u64,
@@ -165,8 +567,13 @@ struct Render2D {
 
 impl Render2D {
     fn new(window: &Window) -> Self {
-        let gpu = futures::executor::block_on(Gpu::new(&window.window));
+        Self::from_gpu(futures::executor::block_on(Gpu::new(
+            &window.window,
+            window.present_mode().to_wgpu(),
+        )))
+    }
 
+    fn from_gpu(gpu: Gpu) -> Self {
         use wgpu::util::DeviceExt;
 
         let quad_vertex_buf = gpu
@@ -193,16 +600,9 @@ impl Render2D {
             mapped_at_creation: false,
         });
 
-        let staging_buf = gpu.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("staging buffer"),
-            size: 128 * MB,
-            usage: wgpu::BufferUsage::MAP_WRITE | wgpu::BufferUsage::COPY_SRC,
-            mapped_at_creation: false,
-        });
-
         let depth_texture = Texture::create_depth_texture(&gpu.device, &gpu.sc_desc);
 
-        let (width, height) = window.resolution();
+        let (width, height) = (gpu.sc_desc.width, gpu.sc_desc.height);
         let viewport = Viewport::new_in_screen(
             width as f32,
             height as f32,
@@ -215,10 +615,11 @@ impl Render2D {
             quad_vertex_buf,
             quad_index_buf,
             utility_buf,
-            staging_buf,
             depth_texture,
 
             viewport,
+
+            extra_targets: HashMap::new(),
             // // NOTE: 临时性数据
             // vhash: 0,
             // fhash: 0,
@@ -226,44 +627,272 @@ impl Render2D {
     }
 
     fn begin_draw(&mut self) {
-        if self.gpu.frame.is_none() {
-            match self.gpu.swap_chain.get_current_frame() {
-                Ok(sw_frame) => self.gpu.frame = Some(sw_frame),
-                Err(err) => panic!("ERR: {}", err),
+        match &mut self.gpu.target {
+            RenderTarget::Window {
+                swap_chain, frame, ..
+            } => {
+                if frame.is_none() {
+                    match swap_chain.get_current_frame() {
+                        Ok(sw_frame) => *frame = Some(sw_frame),
+                        Err(err) => panic!("ERR: {}", err),
+                    }
+                } else {
+                    panic!("ERR: Drawing has begun already.")
+                }
+            }
+            RenderTarget::Offscreen { is_drawing, .. } => {
+                if !*is_drawing {
+                    *is_drawing = true;
+                } else {
+                    panic!("ERR: Drawing has begun already.")
+                }
+            }
+        }
+    }
+
+    /// Create the surfaces of the new extra windows, and drop the ones of the closed windows.
+    fn sync_extra_targets(&mut self, windows: &Windows) {
+        self.extra_targets.retain(|id, _| windows.contains(*id));
+
+        for id in windows.ids() {
+            if self.extra_targets.contains_key(&id) {
+                continue;
             }
+
+            let window = windows.get(id).unwrap();
+            let (width, height) = window.resolution();
+
+            let surface = unsafe { self.gpu.instance.create_surface(&window.window) };
+            let sc_desc = wgpu::SwapChainDescriptor {
+                usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+                // NOTE: The pipelines are created with the format of the primary window.
+                format: self.gpu.sc_desc.format,
+                width: width.max(1),
+                height: height.max(1),
+                present_mode: window.present_mode().to_wgpu(),
+            };
+            let swap_chain = self.gpu.device.create_swap_chain(&surface, &sc_desc);
+            let depth_texture = Texture::create_depth_texture(&self.gpu.device, &sc_desc);
+            let viewport = Viewport::new_in_screen(
+                sc_desc.width as f32,
+                sc_desc.height as f32,
+                Camera2D::default().aspect_ratio(),
+            );
+
+            self.extra_targets.insert(
+                id,
+                ExtraTarget {
+                    target: RenderTarget::Window {
+                        surface,
+                        swap_chain,
+                        frame: None,
+                    },
+                    sc_desc,
+                    depth_texture,
+                    viewport,
+                },
+            );
+        }
+    }
+
+    /// Swap the target of the extra window with the current one, call it again to swap back.
+    fn swap_target(&mut self, id: WindowId) {
+        let extra = self
+            .extra_targets
+            .get_mut(&id)
+            .expect("ERR: Not find the surface of window.");
+
+        mem::swap(&mut self.gpu.target, &mut extra.target);
+        mem::swap(&mut self.gpu.sc_desc, &mut extra.sc_desc);
+        mem::swap(&mut self.depth_texture, &mut extra.depth_texture);
+        mem::swap(&mut self.viewport, &mut extra.viewport);
+    }
+
+    /// Resize the render target and depth texture, or recreate the swap chain with the new
+    /// present mode.
+    fn reconfigure(&mut self, width: u32, height: u32, present_mode: wgpu::PresentMode) {
+        // NOTE: The window is minimized, keep the old size until it's restored.
+        let (width, height) = if width == 0 || height == 0 {
+            (self.gpu.sc_desc.width, self.gpu.sc_desc.height)
         } else {
-            panic!("ERR: Drawing has begun already.")
+            (width, height)
+        };
+
+        if self.gpu.sc_desc.width == width
+            && self.gpu.sc_desc.height == height
+            && self.gpu.sc_desc.present_mode == present_mode
+        {
+            return;
+        }
+
+        self.gpu.sc_desc.width = width;
+        self.gpu.sc_desc.height = height;
+        self.gpu.sc_desc.present_mode = present_mode;
+
+        let Gpu {
+            device,
+            target,
+            sc_desc,
+            ..
+        } = &mut self.gpu;
+
+        match target {
+            RenderTarget::Window {
+                surface,
+                swap_chain,
+                ..
+            } => *swap_chain = device.create_swap_chain(surface, sc_desc),
+            RenderTarget::Offscreen { texture, view, .. } => {
+                let (new_texture, new_view) = Gpu::create_offscreen_texture(device, sc_desc);
+
+                *texture = new_texture;
+                *view = new_view;
+            }
         }
+
+        self.depth_texture = Texture::create_depth_texture(&self.gpu.device, &self.gpu.sc_desc);
     }
 
-    fn process(&mut self, world: &mut World, resources: &mut Resources) -> Transformation {
-        // Get window size.
-        let (width, height) = {
-            let window = resources
-                .get::<Window>()
-                .expect("ERR: Not find window resource.");
-            window.resolution()
+    /// Read the offscreen texture back to CPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the render target is not offscreen.
+    fn capture(&self) -> FrameCapture {
+        let Gpu {
+            device,
+            queue,
+            target,
+            sc_desc,
+            ..
+        } = &self.gpu;
+
+        let texture = match target {
+            RenderTarget::Offscreen { texture, .. } => texture,
+            RenderTarget::Window { .. } => panic!("ERR: Only offscreen target can be captured."),
         };
 
-        // Resize swap_chain and depth texture.
-        if self.gpu.sc_desc.width != width || self.gpu.sc_desc.height != height {
-            self.gpu.sc_desc.width = width;
-            self.gpu.sc_desc.height = height;
+        let (width, height) = (sc_desc.width, sc_desc.height);
+
+        // NOTE: The rows copied from texture to buffer must be aligned to 256 bytes.
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+        let padded_bytes_per_row = u32::try_from(align_to(width as u64 * 4, align))
+            .expect("ERR: The frame is too wide to capture.");
+
+        let readback_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback buffer"),
+            size: padded_bytes_per_row as wgpu::BufferAddress * height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("capture encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &readback_buf,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: padded_bytes_per_row,
+                    rows_per_image: height,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+        );
+
+        queue.submit(Some(encoder.finish()));
 
-            self.gpu.swap_chain = self
-                .gpu
-                .device
-                .create_swap_chain(&self.gpu.surface, &self.gpu.sc_desc);
+        let r_bs = readback_buf.slice(..);
 
-            self.depth_texture = Texture::create_depth_texture(&self.gpu.device, &self.gpu.sc_desc);
+        // Mapping video-memory to main-memory.
+        {
+            let r_ft = r_bs.map_async(wgpu::MapMode::Read);
+            device.poll(wgpu::Maintain::Wait);
+            futures::executor::block_on(r_ft).expect("ERR: map v-mem to m-mem.");
         }
 
-        // Get camera2d.
-        let mut query_camera2d = <(&Transform2D, &Camera2D)>::query();
+        let capture = FrameCapture::from_padded_rows(
+            width,
+            height,
+            padded_bytes_per_row,
+            &r_bs.get_mapped_range(),
+        );
+
+        readback_buf.unmap();
+
+        capture
+    }
+
+    /// Return `None` if no camera targets the window, `window` is `None` for the primary window.
+    fn process(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        window: Option<WindowId>,
+    ) -> Option<Transformation> {
+        // Get target size, follow the window if drawing into the swap chain.
+        let (width, height, present_mode, pixel_ratio) = match self.gpu.target {
+            RenderTarget::Window { .. } => match window {
+                Some(id) => {
+                    let windows = resources
+                        .get::<Windows>()
+                        .expect("ERR: Not find windows resource.");
+                    let window = windows.get(id).expect("ERR: Not find the window.");
+                    let (width, height) = window.resolution();
+
+                    (
+                        width,
+                        height,
+                        window.present_mode().to_wgpu(),
+                        window.pixel_ratio(),
+                    )
+                }
+                None => {
+                    let window = resources
+                        .get::<Window>()
+                        .expect("ERR: Not find window resource.");
+                    let (width, height) = window.resolution();
+
+                    (
+                        width,
+                        height,
+                        window.present_mode().to_wgpu(),
+                        window.pixel_ratio(),
+                    )
+                }
+            },
+            RenderTarget::Offscreen { .. } => (
+                self.gpu.sc_desc.width,
+                self.gpu.sc_desc.height,
+                self.gpu.sc_desc.present_mode,
+                1.0,
+            ),
+        };
+
+        // Resize swap_chain and depth texture, follow the present mode of the window.
+        self.reconfigure(width, height, present_mode);
+        let (width, height) = (self.gpu.sc_desc.width, self.gpu.sc_desc.height);
+
+        // Get the camera2d targets the window.
+        let mut query_camera2d = <(&Transform2D, &Camera2D, Option<&WindowTarget>)>::query();
+        let camera2d = query_camera2d
+            .iter(world)
+            .find(|(_, _, target)| target.map(|target| target.0) == window);
 
         // Render a frame if there has a camera.
         let mut ct = Transformation::default();
-        if let Some((transform2d, camera2d)) = query_camera2d.iter(world).next() {
+        if let Some((transform2d, camera2d, _)) = camera2d {
             ct.mx_view = transform2d.to_homogeneous_3d().try_inverse().unwrap();
             ct.mx_proj = {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -280,9 +909,11 @@ impl Render2D {
                 Viewport::new_in_screen(width as f32, height as f32, camera2d.aspect_ratio());
             ct.mx_viewport = viewport.to_homogeneous_3d();
 
-            let time = resources
+            // NOTE: The offscreen render may run without `Time`.
+            let (t_delta, t_total) = resources
                 .get::<Time>()
-                .expect("ERR: Not find time resource.");
+                .map(|time| (time.delta().as_secs_f32(), time.total().as_secs_f32()))
+                .unwrap_or_default();
 
             // Write matrix data to utility buffer.
             self.gpu.queue.write_buffer(
@@ -312,24 +943,52 @@ impl Render2D {
             self.gpu.queue.write_buffer(
                 &self.utility_buf,
                 200,
-                bytemuck::cast_slice(&[time.delta().as_secs_f32(), time.total().as_secs_f32()]),
+                bytemuck::cast_slice(&[t_delta, t_total]),
+            );
+
+            // Write pixel_ratio to utility buffer.
+            self.gpu.queue.write_buffer(
+                &self.utility_buf,
+                208,
+                bytemuck::cast_slice(&[pixel_ratio]),
             );
 
             self.viewport = viewport;
-        }
 
-        ct
+            Some(ct)
+        } else {
+            None
+        }
     }
 
     fn finish_draw(&mut self) {
-        if self.gpu.frame.is_some() {
-            self.gpu.frame.take();
-        } else {
-            panic!("ERR: Drawing has ended already.")
+        match &mut self.gpu.target {
+            RenderTarget::Window { frame, .. } => {
+                if frame.is_some() {
+                    frame.take();
+                } else {
+                    panic!("ERR: Drawing has ended already.")
+                }
+            }
+            RenderTarget::Offscreen { is_drawing, .. } => {
+                if *is_drawing {
+                    *is_drawing = false;
+                } else {
+                    panic!("ERR: Drawing has ended already.")
+                }
+            }
         }
     }
 }
 
+/// The surface of an extra window, it's swapped into `Render2D` when drawing the window.
+struct ExtraTarget {
+    target: RenderTarget,
+    sc_desc: wgpu::SwapChainDescriptor,
+    depth_texture: Texture,
+    viewport: Viewport,
+}
+
 struct Texture {
     #[allow(dead_code)]
     texture: wgpu::Texture,
@@ -376,3 +1035,236 @@ impl Texture {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset::{AssetServer, Texture},
+        components::{
+            geometry::{
+                BorderDecoration, BorderThickness, Geometry, Geometry1DType, Geometry2DType,
+                InnerDecoration,
+            },
+            sprite::Sprite,
+        },
+    };
+
+    #[test]
+    fn test_align_to() {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+
+        assert_eq!(align_to(0, align), 0);
+        assert_eq!(align_to(4, align), align);
+        assert_eq!(align_to(align, align), align);
+        // NOTE: The widest frame doesn't overflow.
+        assert_eq!(
+            align_to(u32::MAX as u64 * 4, align),
+            (u32::MAX as u64 + 1) * 4
+        );
+    }
+
+    #[test]
+    fn test_frame_capture_from_padded_rows() {
+        // NOTE: The padding bytes are garbage, and the mapped buffer may be longer than the frame.
+        #[rustfmt::skip]
+        let padded: [u8; 40] = [
+            1, 2, 3, 4,     5, 6, 7, 8,     99, 99, 99, 99,
+            9, 10, 11, 12,  13, 14, 15, 16, 99, 99, 99, 99,
+            17, 18, 19, 20, 21, 22, 23, 24, 99, 99, 99, 99,
+            77, 77, 77, 77,
+        ];
+
+        let capture = FrameCapture::from_padded_rows(2, 3, 12, &padded);
+
+        assert_eq!((capture.width(), capture.height()), (2, 3));
+        assert_eq!(
+            capture.data(),
+            &[
+                1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
+                24
+            ]
+        );
+        assert_eq!(capture.pixel(0, 0), Rgba::new(1, 2, 3, 4));
+        assert_eq!(capture.pixel(1, 0), Rgba::new(5, 6, 7, 8));
+        assert_eq!(capture.pixel(0, 1), Rgba::new(9, 10, 11, 12));
+        assert_eq!(capture.pixel(1, 2), Rgba::new(21, 22, 23, 24));
+
+        // NOTE: The rows without padding.
+        let capture = FrameCapture::from_padded_rows(1, 2, 4, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(capture.pixel(0, 1), Rgba::new(5, 6, 7, 8));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_frame_capture_pixel_out_of_frame() {
+        FrameCapture::new(2, 1, vec![0; 8]).pixel(0, 1);
+    }
+
+    #[test]
+    fn test_frame_capture_save_png() {
+        let capture = FrameCapture::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]);
+        let path = std::env::temp_dir().join("yam_test_frame_capture.png");
+
+        capture.save_png(&path).unwrap();
+
+        let image = image::open(&path).unwrap().into_rgba8();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.into_raw(), capture.data());
+    }
+
+    // NOTE: The GPU tests need an adapter(including software adapter), run them by
+    // `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_offscreen_render() {
+        let mut render = OffscreenRender::new(64, 32).expect("ERR: No GPU adapter.");
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        world.push((Transform2D::default(), Camera2D::new(64, 32)));
+
+        render.render(&mut world, &mut resources);
+        let capture = render.capture();
+
+        assert_eq!((capture.width(), capture.height()), (64, 32));
+        assert_eq!(capture.pixel(0, 0).a, 255);
+        assert_eq!(
+            *resources.get::<RenderStats>().unwrap(),
+            RenderStats::default()
+        );
+
+        render.set_resolution(32, 16);
+        render.render(&mut world, &mut resources);
+
+        assert_eq!(render.capture().data().len(), 32 * 16 * 4);
+    }
+
+    /// Set it to write the captures as the golden images instead of comparing with them.
+    const BLESS_GOLDEN: &str = "YAM_BLESS_GOLDEN";
+
+    /// Render the world in 64 x 32 pixels.
+    fn render_64x32(world: &mut World, resources: &mut Resources) -> FrameCapture {
+        let mut render = OffscreenRender::new(64, 32).expect("ERR: No GPU adapter.");
+        world.push((Transform2D::default(), Camera2D::new(64, 32)));
+
+        render.render(world, resources);
+        render.capture()
+    }
+
+    /// Compare the capture with `assets/images/tests/<name>.png`.
+    ///
+    /// NOTE: The golden images are rendered by a real adapter, create or update them by
+    /// `YAM_BLESS_GOLDEN=1 cargo test -- --ignored golden` and check them in.
+    fn assert_golden(capture: &FrameCapture, name: &str) {
+        let path = Path::new("assets/images/tests").join(format!("{}.png", name));
+
+        if std::env::var_os(BLESS_GOLDEN).is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            capture.save_png(&path).unwrap();
+            return;
+        }
+
+        let golden = image::open(&path)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "ERR: Failed to open the golden image {:?}({}), bless it by {}=1.",
+                    path, err, BLESS_GOLDEN
+                )
+            })
+            .into_rgba8();
+
+        assert_eq!(golden.dimensions(), (capture.width(), capture.height()));
+
+        // NOTE: Tolerate the rounding of different GPUs.
+        for (i, (a, b)) in capture.data().iter().zip(golden.as_raw()).enumerate() {
+            assert!(
+                (*a as i32 - *b as i32).abs() <= 1,
+                "ERR: The pixel {} differs from the golden image {:?}.",
+                i / 4,
+                path
+            );
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn test_golden_background() {
+        let capture = render_64x32(&mut World::default(), &mut Resources::default());
+
+        assert_golden(&capture, "background");
+    }
+
+    #[test]
+    #[ignore]
+    fn test_golden_geometry() {
+        let mut world = World::default();
+
+        world.push((
+            Transform2D::default(),
+            Geometry::new_2d(
+                Geometry2DType::Circle,
+                BorderDecoration::Solid,
+                Rgba::SOFT_BLACK,
+                BorderThickness::LocalSpace(2.0),
+                InnerDecoration::Solid,
+                Rgba::ORANGE,
+                100,
+                Vector2::new(-12.0, 0.0),
+                0.0,
+                24.0,
+            ),
+        ));
+        world.push((
+            Transform2D::default(),
+            Geometry::new_1d(
+                Geometry1DType::Segment,
+                BorderDecoration::Solid,
+                Rgba::BLUE,
+                BorderThickness::LocalSpace(2.0),
+                100,
+                Vector2::new(4.0, -10.0),
+                Vector2::new(28.0, 10.0),
+            ),
+        ));
+
+        let capture = render_64x32(&mut world, &mut Resources::default());
+
+        assert_golden(&capture, "geometry");
+    }
+
+    #[test]
+    #[ignore]
+    fn test_golden_sprite() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        // NOTE: A 2x2 texture stretched over the middle of the frame, the background is visible
+        // around it.
+        #[rustfmt::skip]
+        let texture = Texture::new(2, 2, vec![
+            255, 0, 0, 255,  128, 128, 128, 255,
+            0, 0, 255, 255,  64, 192, 32, 255,
+        ]);
+        let mut asset_server = AssetServer::default();
+        let handle = asset_server.add_texture(texture);
+        resources.insert(asset_server);
+
+        world.push((Transform2D::with_scale(32.0, 16.0), Sprite::new(handle)));
+
+        let capture = render_64x32(&mut world, &mut resources);
+
+        // NOTE: The gray texel is written unconverted, a wrong color space makes it darker.
+        let gray = capture.pixel(40, 12);
+        assert!(
+            (gray.r as i32 - 128).abs() <= 1,
+            "ERR: {:?} is not gray.",
+            gray
+        );
+
+        assert_golden(&capture, "sprite");
+    }
+}
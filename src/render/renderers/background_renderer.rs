@@ -122,12 +122,7 @@ impl BackgroundRenderer {
     }
 
     pub fn render(&mut self, r2d: &Render2D, _world: &World, _resources: &Resources) {
-        let Gpu {
-            device,
-            queue,
-            frame,
-            ..
-        } = &r2d.gpu;
+        let Gpu { device, queue, .. } = &r2d.gpu;
 
         let Viewport {
             x,
@@ -138,7 +133,7 @@ impl BackgroundRenderer {
             max_depth,
         } = r2d.viewport;
 
-        let frame = frame.as_ref().expect("ERR: Not call begin_draw");
+        let frame_view = r2d.gpu.frame_view().expect("ERR: Not call begin_draw");
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("background encoder"),
@@ -149,7 +144,7 @@ impl BackgroundRenderer {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("background render pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &frame.output.view,
+                    attachment: frame_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(Rgba::SOFT_BLACK.to_wgpu_color()),
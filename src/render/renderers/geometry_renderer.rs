@@ -1,4 +1,4 @@
-use super::super::{Gpu, Render2D, Viewport, MILLION};
+use super::super::{align_to, DebugDraw, Gpu, Render2D, Viewport, MAX_BINDING_SIZE, MILLION};
 
 use crate::{
     components::{
@@ -12,23 +12,110 @@ use crate::{
 
 use std::mem::size_of;
 
-#[rustfmt::skip] const MAX_TRANSFORM2D_COUNT:   usize = 2 * MILLION;
-#[rustfmt::skip] const MAX_GEOMETRY_COUNT:      usize = 2 * MILLION;
-#[rustfmt::skip] const MAX_INDEX_PAIR_COUNT:    usize = 2 * MILLION;
+#[rustfmt::skip] const INIT_TRANSFORM2D_COUNT:  usize = 2 * MILLION;
+#[rustfmt::skip] const INIT_GEOMETRY_COUNT:     usize = 2 * MILLION;
+#[rustfmt::skip] const INIT_INDEX_PAIR_COUNT:   usize = 2 * MILLION;
 
-#[rustfmt::skip] const TRANSFORM2D_BUF_SIZE:    u64 = (size_of::<Transform2D>() * MAX_TRANSFORM2D_COUNT) as u64;
-#[rustfmt::skip] const GEOMETRY_BUF_SIZE:       u64 = (size_of::<Geometry>() * MAX_GEOMETRY_COUNT) as u64;
-#[rustfmt::skip] const INDEX_PAIR_BUF_SIZE:     u64 = (size_of::<(u32, u32)>() * MAX_INDEX_PAIR_COUNT) as u64;
+/// The offsets of storage buffer bindings must be aligned to it.
+const STORAGE_ALIGNMENT: u64 = 256;
+
+type IndexPair = [u32; 2];
+
+/// The count of `Transform2D`, `Geometry` and `Index Pair` in a frame,
+/// or the count the buffers can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Capacity {
+    t: usize,
+    g: usize,
+    i: usize,
+}
+
+impl Capacity {
+    /// Size of `Transform2D` part, aligned to `STORAGE_ALIGNMENT`.
+    fn t_buf_size(&self) -> u64 {
+        align_to(
+            (size_of::<Transform2D>() * self.t) as u64,
+            STORAGE_ALIGNMENT,
+        )
+    }
+
+    /// Size of `Geometry` part, aligned to `STORAGE_ALIGNMENT`.
+    fn g_buf_size(&self) -> u64 {
+        align_to((size_of::<Geometry>() * self.g) as u64, STORAGE_ALIGNMENT)
+    }
+
+    /// Size of `Index Pair` part, aligned to `STORAGE_ALIGNMENT`.
+    fn i_buf_size(&self) -> u64 {
+        align_to((size_of::<IndexPair>() * self.i) as u64, STORAGE_ALIGNMENT)
+    }
+
+    /// The largest capacity whose buffers fit `MAX_BINDING_SIZE`.
+    fn max() -> Capacity {
+        Capacity {
+            t: (MAX_BINDING_SIZE / size_of::<Transform2D>() as u64) as usize,
+            g: (MAX_BINDING_SIZE / size_of::<Geometry>() as u64) as usize,
+            i: (MAX_BINDING_SIZE / size_of::<IndexPair>() as u64) as usize,
+        }
+    }
+
+    fn contains(&self, other: &Capacity) -> bool {
+        self.t >= other.t && self.g >= other.g && self.i >= other.i
+    }
+
+    fn min(&self, other: &Capacity) -> Capacity {
+        Capacity {
+            t: self.t.min(other.t),
+            g: self.g.min(other.g),
+            i: self.i.min(other.i),
+        }
+    }
+
+    /// Return the capacity which can hold `need`, grow by power of two but never beyond `max`.
+    fn grow_to_fit(&self, need: &Capacity, max: &Capacity) -> Capacity {
+        fn grow(cur: usize, need: usize, max: usize) -> usize {
+            if cur >= need {
+                cur
+            } else {
+                need.next_power_of_two().min(max)
+            }
+        }
+
+        Capacity {
+            t: grow(self.t, need.t, max.t),
+            g: grow(self.g, need.g, max.g),
+            i: grow(self.i, need.i, max.i),
+        }
+    }
+}
+
+/// The part of a frame which fits the buffers, drawn in a draw call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Batch {
+    /// The counts written to the buffers.
+    written: Capacity,
+    /// The index of the item the next batch starts with, `None` if the frame is done.
+    next: Option<usize>,
+    /// The count of instances whose item cannot fit even an empty batch.
+    dropped: usize,
+}
 
 /// Renderer which renders `Geometry2D` in the best performance.
 pub(in super::super) struct GeometryRenderer {
+    /// The count of datas the buffers can hold, grow when a frame needs more.
+    capacity: Capacity,
+
     instance_buf: wgpu::Buffer,
     /// Store `Transform2D` data and `Geometry` data.
     ///
-    /// Default size: `TRANSFORM2D_BUF_SIZE + GEOMETRY_BUF_SIZE`.
+    /// Size: `capacity.t_buf_size() + capacity.g_buf_size()`.
     storage_buf: wgpu::Buffer,
+    /// A springboard to transfer data from CPU to GPU.
+    ///
+    /// Size: `capacity.t_buf_size() + capacity.g_buf_size() + capacity.i_buf_size()`.
+    staging_buf: wgpu::Buffer,
 
     // For `Geometry2D` rendering.
+    bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
     pipeline: wgpu::RenderPipeline,
 }
@@ -39,19 +126,13 @@ impl GeometryRenderer {
             device, sc_desc, ..
         } = &r2d.gpu;
 
-        let instance_buf = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("instance buffer"),
-            size: INDEX_PAIR_BUF_SIZE,
-            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let capacity = Capacity {
+            t: INIT_TRANSFORM2D_COUNT,
+            g: INIT_GEOMETRY_COUNT,
+            i: INIT_INDEX_PAIR_COUNT,
+        };
 
-        let storage_buf = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("storage buffer"),
-            size: TRANSFORM2D_BUF_SIZE + GEOMETRY_BUF_SIZE,
-            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let (instance_buf, storage_buf, staging_buf) = Self::create_buffers(device, &capacity);
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("geometry bind group layout"),
@@ -89,36 +170,8 @@ impl GeometryRenderer {
             ],
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("geometry bind group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &r2d.utility_buf,
-                        offset: 0,
-                        size: None,
-                    },
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &storage_buf,
-                        offset: 0,
-                        size: wgpu::BufferSize::new(TRANSFORM2D_BUF_SIZE),
-                    },
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &storage_buf,
-                        offset: TRANSFORM2D_BUF_SIZE,
-                        size: wgpu::BufferSize::new(GEOMETRY_BUF_SIZE),
-                    },
-                },
-            ],
-        });
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, r2d, &storage_buf, &capacity);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("geometry pipeline layout"),
@@ -155,7 +208,7 @@ impl GeometryRenderer {
                         attributes: &wgpu::vertex_attr_array![0 => Float4],
                     },
                     wgpu::VertexBufferLayout {
-                        array_stride: size_of::<(u32, u32)>() as wgpu::BufferAddress,
+                        array_stride: size_of::<IndexPair>() as wgpu::BufferAddress,
                         step_mode: wgpu::InputStepMode::Instance,
                         attributes: &wgpu::vertex_attr_array![1 => Uint2],
                     },
@@ -196,21 +249,113 @@ impl GeometryRenderer {
         });
 
         Self {
+            capacity,
+
             instance_buf,
             storage_buf,
+            staging_buf,
 
+            bind_group_layout,
             bind_group,
             pipeline,
         }
     }
 
-    pub fn render(&mut self, r2d: &Render2D, world: &World, _resources: &Resources) {
-        let Gpu {
+    fn create_buffers(
+        device: &wgpu::Device,
+        capacity: &Capacity,
+    ) -> (wgpu::Buffer, wgpu::Buffer, wgpu::Buffer) {
+        let instance_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("instance buffer"),
+            size: capacity.i_buf_size(),
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let storage_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("storage buffer"),
+            size: capacity.t_buf_size() + capacity.g_buf_size(),
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let staging_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging buffer"),
+            size: capacity.t_buf_size() + capacity.g_buf_size() + capacity.i_buf_size(),
+            usage: wgpu::BufferUsage::MAP_WRITE | wgpu::BufferUsage::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        (instance_buf, storage_buf, staging_buf)
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        r2d: &Render2D,
+        storage_buf: &wgpu::Buffer,
+        capacity: &Capacity,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("geometry bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &r2d.utility_buf,
+                        offset: 0,
+                        size: None,
+                    },
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: storage_buf,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(capacity.t_buf_size()),
+                    },
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: storage_buf,
+                        offset: capacity.t_buf_size(),
+                        size: wgpu::BufferSize::new(capacity.g_buf_size()),
+                    },
+                },
+            ],
+        })
+    }
+
+    /// Grow the buffers(and recreate the bind group) if they cannot hold `need`.
+    fn reserve(&mut self, r2d: &Render2D, need: &Capacity) {
+        if self.capacity.contains(need) {
+            return;
+        }
+
+        let device = &r2d.gpu.device;
+
+        self.capacity = self.capacity.grow_to_fit(need, &Capacity::max());
+
+        let (instance_buf, storage_buf, staging_buf) = Self::create_buffers(device, &self.capacity);
+
+        self.bind_group = Self::create_bind_group(
             device,
-            queue,
-            frame,
-            ..
-        } = &r2d.gpu;
+            &self.bind_group_layout,
+            r2d,
+            &storage_buf,
+            &self.capacity,
+        );
+
+        self.instance_buf = instance_buf;
+        self.storage_buf = storage_buf;
+        self.staging_buf = staging_buf;
+    }
+
+    /// Return the count of instances dropped, see `Batch::dropped`.
+    pub fn render(&mut self, r2d: &Render2D, world: &World, resources: &Resources) -> usize {
+        let Gpu { device, queue, .. } = &r2d.gpu;
 
         let Viewport {
             x,
@@ -221,77 +366,157 @@ impl GeometryRenderer {
             max_depth,
         } = r2d.viewport;
 
-        let frame = frame.as_ref().expect("ERR: Not call begin_draw.");
+        let frame_view = r2d.gpu.frame_view().expect("ERR: Not call begin_draw.");
 
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("general encoder"),
-        });
+        let debug_draw = resources.get::<DebugDraw>();
+        let debug = debug_draw.as_deref().map_or(&[][..], DebugDraw::geometries);
 
-        let (i_count, i_buf_size) = self.copy_data_to_gpu(&mut encoder, r2d, world);
+        let need = Self::count(world, debug);
+        self.reserve(r2d, &need.min(&Capacity::max()));
 
-        encoder.insert_debug_marker("render geometry");
-        {
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("geometry render pass"),
-                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &(frame.output.view),
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: true,
-                    },
-                }],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
-                    attachment: &r2d.depth_texture.view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: false,
-                    }),
-                    stencil_ops: None,
-                }),
+        // Nothing to draw if no instance.
+        let mut next = if need.i > 0 { Some(0) } else { None };
+        let mut dropped = 0;
+        let mut is_first_draw = true;
+
+        // NOTE: The frame which exceeds the buffers is split into batches, every batch reuses
+        // the buffers and draws after the previous one.
+        while let Some(start) = next {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("general encoder"),
             });
 
-            rpass.push_debug_group("Set datas");
+            let (batch, i_buf_size) = self.copy_data_to_gpu(&mut encoder, r2d, world, debug, start);
+            dropped += batch.dropped;
+
+            if batch.written.i > 0 {
+                encoder.insert_debug_marker("render geometry");
+
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("geometry render pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: frame_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }],
+                    depth_stencil_attachment: Some(
+                        wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                            attachment: &r2d.depth_texture.view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: if is_first_draw {
+                                    wgpu::LoadOp::Clear(1.0)
+                                } else {
+                                    wgpu::LoadOp::Load
+                                },
+                                store: batch.next.is_some(),
+                            }),
+                            stencil_ops: None,
+                        },
+                    ),
+                });
+
+                rpass.push_debug_group("Set datas");
+
+                rpass.set_pipeline(&self.pipeline);
+                rpass.set_viewport(x, y, w, h, min_depth, max_depth);
+                rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
+                rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
+                rpass.set_vertex_buffer(1, self.instance_buf.slice(0..i_buf_size));
+                rpass.set_bind_group(0, &self.bind_group, &[]);
+
+                rpass.pop_debug_group();
+
+                rpass.draw_indexed(0..6, 0, 0..batch.written.i as u32);
+
+                is_first_draw = false;
+            }
+
+            queue.submit(Some(encoder.finish()));
 
-            rpass.set_pipeline(&self.pipeline);
-            rpass.set_viewport(x, y, w, h, min_depth, max_depth);
-            rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
-            rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
-            rpass.set_vertex_buffer(1, self.instance_buf.slice(0..i_buf_size));
-            rpass.set_bind_group(0, &self.bind_group, &[]);
+            next = batch.next;
+        }
 
-            rpass.pop_debug_group();
+        drop(debug_draw);
 
-            rpass.draw_indexed(0..6, 0, 0..i_count as u32);
+        // The shapes of `DebugDraw` only live for a frame.
+        if let Some(mut debug_draw) = resources.get_mut::<DebugDraw>() {
+            debug_draw.clear();
         }
 
-        queue.submit(Some(encoder.finish()));
+        dropped
     }
 
-    /// Collect `Transform2D`, `Geometry` and calculate `Index Pair`, then
-    /// copy them to the memory of video card.
+    /// Count `Transform2D`, `Geometry` and `Index Pair` which will be copied in this frame.
     ///
-    /// Return instance count and instance size.
+    /// `debug` are the geometries in `world space` from `DebugDraw`.
+    fn count(world: &World, debug: &[Geometry]) -> Capacity {
+        let mut need = Capacity { t: 0, g: 0, i: 0 };
+
+        let mut q01 = <(&Transform2D, &Geometry)>::query();
+        let mut q02 = <(&Transform2D, &Assembly)>::query();
+        let mut q03 = <(&Instance<Transform2D>, &Geometry)>::query();
+        let mut q04 = <(&Instance<Transform2D>, &Assembly)>::query();
+
+        q01.for_each(world, |_| {
+            need.t += 1;
+            need.g += 1;
+            need.i += 1;
+        });
+
+        q02.for_each(world, |(_, gs)| {
+            need.t += 1;
+            need.g += gs.len();
+            need.i += gs.len();
+        });
+
+        q03.for_each(world, |(ts, _)| {
+            need.t += ts.len();
+            need.g += 1;
+            need.i += ts.len();
+        });
+
+        q04.for_each(world, |(ts, gs)| {
+            need.t += ts.len();
+            need.g += gs.len();
+            need.i += ts.len() * gs.len();
+        });
+
+        // All debug geometries share an identity `Transform2D`.
+        if !debug.is_empty() {
+            need.t += 1;
+            need.g += debug.len();
+            need.i += debug.len();
+        }
+
+        need
+    }
+
+    /// Collect `Transform2D`, `Geometry` and calculate `Index Pair` of a batch from the
+    /// `start`-th item, then copy them to the memory of video card.
     ///
-    /// #Panics
+    /// The buffers must be reserved for the frame first.
     ///
-    /// Panic if
-    ///     1. The number of `Transform2D` exceeds the limit: `MAX_TRANSFORM2D_COUNT`.
-    ///     2. The number of `Geometry` exceeds the limit: `MAX_GEOMETRY_COUNT`.
-    ///     3. The number of `Index Pair` exceeds the limit: `MAX_INDEX_PAIR_COUNT`.
+    /// Return the batch and instance size.
     fn copy_data_to_gpu(
         &mut self,
         encoder: &mut wgpu::CommandEncoder,
         r2d: &Render2D,
         world: &World,
-    ) -> (usize, wgpu::BufferAddress) {
+        debug: &[Geometry],
+        start: usize,
+    ) -> (Batch, wgpu::BufferAddress) {
         let Gpu { device, .. } = &r2d.gpu;
 
+        let capacity = self.capacity;
+
         let t_st = 0;
-        let g_st = TRANSFORM2D_BUF_SIZE;
-        let i_st = TRANSFORM2D_BUF_SIZE + GEOMETRY_BUF_SIZE;
+        let g_st = capacity.t_buf_size();
+        let i_st = capacity.t_buf_size() + capacity.g_buf_size();
 
-        let s_bs = r2d.staging_buf.slice(..);
+        let s_bs = self.staging_buf.slice(..);
 
         // Mapping main-memory to video-memory.
         {
@@ -300,140 +525,306 @@ impl GeometryRenderer {
             futures::executor::block_on(s_ft).expect("ERR: map m-mem to v-mem.");
         }
 
-        // Get `Transform2D` slice, `Geometry` slice, and `Index Pair` slice on the mapped buffer.
-        let (t_slice, g_slice, i_slice) = unsafe {
-            let s_mp = s_bs.get_mapped_range_mut().as_mut_ptr();
+        let batch = {
+            let mut s_mr = s_bs.get_mapped_range_mut();
 
-            let t_mp = s_mp.offset(t_st as isize) as *mut Transform2D;
-            let g_mp = s_mp.offset(g_st as isize) as *mut Geometry;
-            let i_mp = s_mp.offset(i_st as isize) as *mut (u32, u32);
+            // Get `Transform2D` slice, `Geometry` slice, and `Index Pair` slice on the mapped buffer.
+            let (t_bytes, rest) = s_mr.split_at_mut(g_st as usize);
+            let (g_bytes, i_bytes) = rest.split_at_mut((i_st - g_st) as usize);
 
-            let t_len = TRANSFORM2D_BUF_SIZE as usize / size_of::<Transform2D>();
-            let g_len = GEOMETRY_BUF_SIZE as usize / size_of::<Geometry>();
-            let i_len = INDEX_PAIR_BUF_SIZE as usize / size_of::<(u32, u32)>();
+            let t_slice: &mut [Transform2D] =
+                bytemuck::cast_slice_mut(&mut t_bytes[..capacity.t * size_of::<Transform2D>()]);
+            let g_slice: &mut [Geometry] =
+                bytemuck::cast_slice_mut(&mut g_bytes[..capacity.g * size_of::<Geometry>()]);
+            let i_slice: &mut [IndexPair] =
+                bytemuck::cast_slice_mut(&mut i_bytes[..capacity.i * size_of::<IndexPair>()]);
 
-            (
-                std::slice::from_raw_parts_mut(t_mp, t_len),
-                std::slice::from_raw_parts_mut(g_mp, g_len),
-                std::slice::from_raw_parts_mut(i_mp, i_len),
-            )
+            Self::collect(world, debug, start, t_slice, g_slice, i_slice)
         };
 
-        let mut t_count: usize = 0;
-        let mut g_count: usize = 0;
-        let mut i_count: usize = 0;
+        self.staging_buf.unmap();
 
-        // Copy `Transform2D` and `Geometry` data from `World` to the buffer which is mapped to staging_buf.
-        unsafe {
-            let mut q01 = <(&Transform2D, &Geometry)>::query();
-            let mut q02 = <(&Transform2D, &Assembly)>::query();
-            let mut q03 = <(&Instance<Transform2D>, &Geometry)>::query();
-            let mut q04 = <(&Instance<Transform2D>, &Assembly)>::query();
+        let t_buf_size = (batch.written.t * size_of::<Transform2D>()) as wgpu::BufferAddress;
+        let g_buf_size = (batch.written.g * size_of::<Geometry>()) as wgpu::BufferAddress;
+        let i_buf_size = (batch.written.i * size_of::<IndexPair>()) as wgpu::BufferAddress;
 
-            q01.for_each(world, |(t, g)| {
-                *t_slice.get_unchecked_mut(t_count) = *t;
-                *g_slice.get_unchecked_mut(g_count) = *g;
-                *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+        // Copy transform2d data from staging to storage.
+        encoder.copy_buffer_to_buffer(&self.staging_buf, t_st, &self.storage_buf, 0, t_buf_size);
+        // Copy geometry data from staging to storage.
+        encoder.copy_buffer_to_buffer(&self.staging_buf, g_st, &self.storage_buf, g_st, g_buf_size);
+        // Copy index pair data from staging to instance.
+        encoder.copy_buffer_to_buffer(&self.staging_buf, i_st, &self.instance_buf, 0, i_buf_size);
 
-                t_count += 1;
-                g_count += 1;
-                i_count += 1;
-            });
+        (batch, i_buf_size)
+    }
 
-            q02.for_each(world, |(t, gs)| {
-                *t_slice.get_unchecked_mut(t_count) = *t;
+    /// Copy `Transform2D` and `Geometry` data of a batch from `World` to the slices, and
+    /// calculate `Index Pair`.
+    ///
+    /// An item is some `Transform2D`s with some `Geometry`s of an entity(or `DebugDraw`), every
+    /// `Geometry` is drawn with every `Transform2D`. The batch starts with the `start`-th item
+    /// and ends before the first item which doesn't fit the rest of the slices.
+    fn collect(
+        world: &World,
+        debug: &[Geometry],
+        start: usize,
+        t_slice: &mut [Transform2D],
+        g_slice: &mut [Geometry],
+        i_slice: &mut [IndexPair],
+    ) -> Batch {
+        let mut collector = Collector {
+            t_slice,
+            g_slice,
+            i_slice,
+            start,
+            item: 0,
+            batch: Batch {
+                written: Capacity { t: 0, g: 0, i: 0 },
+                next: None,
+                dropped: 0,
+            },
+        };
 
-                let g_len = gs.len();
+        let mut q01 = <(&Transform2D, &Geometry)>::query();
+        let mut q02 = <(&Transform2D, &Assembly)>::query();
+        let mut q03 = <(&Instance<Transform2D>, &Geometry)>::query();
+        let mut q04 = <(&Instance<Transform2D>, &Assembly)>::query();
 
-                let g_part = &mut g_slice[g_count..g_count + g_len];
-                g_part.copy_from_slice(gs);
+        q01.for_each(world, |(t, g)| {
+            collector.push(std::slice::from_ref(t), std::slice::from_ref(g))
+        });
+        q02.for_each(world, |(t, gs)| collector.push(std::slice::from_ref(t), gs));
+        q03.for_each(world, |(ts, g)| collector.push(ts, std::slice::from_ref(g)));
+        q04.for_each(world, |(ts, gs)| collector.push(ts, gs));
 
-                for _ in 0..g_len {
-                    *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+        // All debug geometries share an identity `Transform2D`.
+        if !debug.is_empty() {
+            collector.push(&[Transform2D::default()], debug);
+        }
 
-                    g_count += 1;
-                    i_count += 1;
-                }
+        collector.batch
+    }
+}
 
-                t_count += 1;
-            });
+/// Write the items of a batch to the slices, see `GeometryRenderer::collect`.
+struct Collector<'a> {
+    t_slice: &'a mut [Transform2D],
+    g_slice: &'a mut [Geometry],
+    i_slice: &'a mut [IndexPair],
+    /// The items before it are drawn by the previous batches.
+    start: usize,
+    /// The index of the next item.
+    item: usize,
+    batch: Batch,
+}
 
-            q03.for_each(world, |(ts, g)| {
-                let t_len = ts.len();
+impl<'a> Collector<'a> {
+    fn push(&mut self, ts: &[Transform2D], gs: &[Geometry]) {
+        let item = self.item;
+        self.item += 1;
 
-                let t_part = &mut t_slice[t_count..t_count + t_len];
-                t_part.copy_from_slice(ts);
+        // Skip the items of the previous batches and the next batches.
+        if item < self.start || self.batch.next.is_some() {
+            return;
+        }
 
-                *g_slice.get_unchecked_mut(g_count) = *g;
+        let Capacity { t, g, i } = self.batch.written;
+        let (t_len, g_len) = (ts.len(), gs.len());
 
-                for _ in 0..t_len {
-                    *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+        let max = Capacity {
+            t: self.t_slice.len(),
+            g: self.g_slice.len(),
+            i: self.i_slice.len(),
+        };
 
-                    t_count += 1;
-                    i_count += 1;
-                }
+        if !max.contains(&Capacity {
+            t: t + t_len,
+            g: g + g_len,
+            i: i + t_len * g_len,
+        }) {
+            // NOTE: The item larger than the slices can never be drawn, drop it instead of
+            // starting an empty batch with it again and again.
+            if self.batch.written == (Capacity { t: 0, g: 0, i: 0 }) {
+                self.batch.dropped += t_len * g_len;
+            } else {
+                self.batch.next = Some(item);
+            }
+            return;
+        }
 
-                g_count += 1;
-            });
+        self.t_slice[t..t + t_len].copy_from_slice(ts);
+        self.g_slice[g..g + g_len].copy_from_slice(gs);
 
-            q04.for_each(world, |(ts, gs)| {
-                let t_len = ts.len();
-                let g_len = gs.len();
+        let mut i_count = i;
+        for t_index in t..t + t_len {
+            for g_index in g..g + g_len {
+                self.i_slice[i_count] = [t_index as u32, g_index as u32];
 
-                let t_part = &mut t_slice[t_count..t_count + t_len];
-                let g_part = &mut g_slice[g_count..g_count + g_len];
+                i_count += 1;
+            }
+        }
 
-                t_part.copy_from_slice(ts);
-                g_part.copy_from_slice(gs);
+        self.batch.written = Capacity {
+            t: t + t_len,
+            g: g + g_len,
+            i: i_count,
+        };
+    }
+}
 
-                for t in 0..t_len {
-                    for g in 0..g_len {
-                        *i_slice.get_unchecked_mut(i_count) =
-                            ((t_count + t) as u32, (g_count + g) as u32);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                        i_count += 1;
-                    }
-                }
+    #[test]
+    fn test_capacity_grow() {
+        let capacity = Capacity { t: 4, g: 4, i: 4 };
 
-                t_count += t_len;
-                g_count += g_len;
-            });
+        assert!(capacity.contains(&Capacity { t: 4, g: 1, i: 0 }));
+        assert!(!capacity.contains(&Capacity { t: 4, g: 5, i: 0 }));
 
-            if t_count > MAX_TRANSFORM2D_COUNT {
-                panic!(
-                    "ERR: The number of Transform2D exceeds the limit: {}",
-                    MAX_TRANSFORM2D_COUNT
-                );
-            }
+        let grown = capacity.grow_to_fit(&Capacity { t: 2, g: 5, i: 100 }, &Capacity::max());
+
+        assert_eq!(grown, Capacity { t: 4, g: 8, i: 128 });
+        assert_eq!(grown.t_buf_size() % STORAGE_ALIGNMENT, 0);
+        assert_eq!(grown.g_buf_size() % STORAGE_ALIGNMENT, 0);
 
-            if g_count > MAX_GEOMETRY_COUNT {
-                panic!(
-                    "ERR: The number of Geometry exceeds the limit: {}",
-                    MAX_GEOMETRY_COUNT
-                );
+        let max = Capacity::max();
+        let need = Capacity {
+            t: max.t + 1,
+            g: 5,
+            i: max.i * 2,
+        };
+        let grown = capacity.grow_to_fit(&need.min(&max), &max);
+
+        assert_eq!(
+            grown,
+            Capacity {
+                t: max.t,
+                g: 8,
+                i: max.i
             }
+        );
+        assert!(grown.t_buf_size() <= MAX_BINDING_SIZE);
+        assert!(grown.g_buf_size() <= MAX_BINDING_SIZE);
+        assert!(grown.i_buf_size() <= MAX_BINDING_SIZE);
+    }
 
-            if i_count > MAX_INDEX_PAIR_COUNT {
-                panic!(
-                    "ERR: The number of Index_Pair exceeds the limit: {}",
-                    MAX_INDEX_PAIR_COUNT
-                );
+    #[test]
+    fn test_collect() {
+        let mut world = World::default();
+
+        let g = Geometry::new_2d(
+            crate::Geometry2DType::Circle,
+            crate::BorderDecoration::None,
+            crate::Rgba::WHITE,
+            crate::BorderThickness::LocalSpace(1.0),
+            crate::InnerDecoration::Solid,
+            crate::Rgba::WHITE,
+            0,
+            crate::nalgebra::Vector2::new(0.0, 0.0),
+            0.0,
+            1.0,
+        );
+
+        world.push((Transform2D::default(), g));
+        world.push((Transform2D::default(), vec![g, g]));
+        world.push((vec![Transform2D::default(); 3], g));
+        world.push((vec![Transform2D::default(); 2], vec![g, g]));
+
+        let need = GeometryRenderer::count(&world, &[g, g]);
+        assert_eq!(need, Capacity { t: 8, g: 8, i: 12 });
+
+        let mut t_slice = vec![Transform2D::default(); need.t];
+        let mut g_slice = vec![g; need.g];
+        let mut i_slice = vec![[0u32; 2]; need.i];
+
+        let batch =
+            GeometryRenderer::collect(&world, &[g, g], 0, &mut t_slice, &mut g_slice, &mut i_slice);
+        assert_eq!(
+            batch,
+            Batch {
+                written: need,
+                next: None,
+                dropped: 0
             }
+        );
+
+        for [t, g] in i_slice {
+            assert!((t as usize) < need.t);
+            assert!((g as usize) < need.g);
         }
+    }
 
-        r2d.staging_buf.unmap();
+    #[test]
+    fn test_collect_batches() {
+        let mut world = World::default();
+
+        let g = Geometry::new_2d(
+            crate::Geometry2DType::Circle,
+            crate::BorderDecoration::None,
+            crate::Rgba::WHITE,
+            crate::BorderThickness::LocalSpace(1.0),
+            crate::InnerDecoration::Solid,
+            crate::Rgba::WHITE,
+            0,
+            crate::nalgebra::Vector2::new(0.0, 0.0),
+            0.0,
+            1.0,
+        );
+
+        world.push((Transform2D::default(), g));
+        world.push((Transform2D::default(), vec![g, g]));
+        world.push((vec![Transform2D::default(); 3], g));
+        // NOTE: More `Transform2D` than the slices can hold, so it's dropped.
+        world.push((vec![Transform2D::default(); 5], g));
+        world.push((vec![Transform2D::default(); 2], vec![g, g]));
+
+        let need = GeometryRenderer::count(&world, &[g, g]);
+        assert_eq!(need, Capacity { t: 13, g: 9, i: 17 });
+
+        let fit = Capacity { t: 4, g: 4, i: 4 };
+        let mut t_slice = vec![Transform2D::default(); fit.t];
+        let mut g_slice = vec![g; fit.g];
+        let mut i_slice = vec![[u32::MAX; 2]; fit.i];
+
+        let mut start = 0;
+        let mut drawn = Vec::new();
+        let mut dropped = 0;
+
+        loop {
+            let batch = GeometryRenderer::collect(
+                &world,
+                &[g, g],
+                start,
+                &mut t_slice,
+                &mut g_slice,
+                &mut i_slice,
+            );
+
+            assert!(fit.contains(&batch.written));
+            assert!(batch.written.i > 0);
+
+            for [t, g] in &i_slice[..batch.written.i] {
+                assert!((*t as usize) < batch.written.t);
+                assert!((*g as usize) < batch.written.g);
+            }
 
-        let t_buf_size = (t_count * size_of::<Transform2D>()) as wgpu::BufferAddress;
-        let g_buf_size = (g_count * size_of::<Geometry>()) as wgpu::BufferAddress;
-        let i_buf_size = (i_count * size_of::<(u32, u32)>()) as wgpu::BufferAddress;
+            drawn.push(batch.written.i);
+            dropped += batch.dropped;
 
-        // Copy transform2d data from staging to storage.
-        encoder.copy_buffer_to_buffer(&r2d.staging_buf, t_st, &self.storage_buf, 0, t_buf_size);
-        // Copy geometry data from staging to storage.
-        encoder.copy_buffer_to_buffer(&r2d.staging_buf, g_st, &self.storage_buf, g_st, g_buf_size);
-        // Copy index pair data from staging to instance.
-        encoder.copy_buffer_to_buffer(&r2d.staging_buf, i_st, &self.instance_buf, 0, i_buf_size);
+            match batch.next {
+                Some(next) => {
+                    assert!(next > start);
+                    start = next;
+                }
+                None => break,
+            }
+        }
 
-        (i_count, i_buf_size)
+        // NOTE: Every item is drawn once except the dropped one.
+        assert_eq!(drawn.iter().sum::<usize>() + dropped, need.i);
+        assert_eq!(dropped, 5);
+        assert_eq!(drawn, [3, 3, 4, 2]);
     }
 }
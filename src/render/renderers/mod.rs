@@ -1,2 +1,3 @@
 pub mod background_renderer;
 pub mod geometry_renderer;
+pub mod sprite_renderer;
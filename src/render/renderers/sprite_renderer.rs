@@ -0,0 +1,501 @@
+use super::super::{Gpu, Render2D, Viewport, MAX_BINDING_SIZE, THOUSAND};
+
+use crate::{
+    asset::{AssetServer, Assets, Handle, Texture},
+    components::{sprite::Sprite, transform::Transform2D},
+    legion::{IntoQuery, Resources, World},
+    misc::color::Hex,
+    nalgebra::Vector4,
+    Instance,
+};
+
+use std::{collections::HashMap, mem::size_of, ops::Range};
+
+#[rustfmt::skip] const INIT_SPRITE_COUNT: usize = 64 * THOUSAND;
+/// The count of sprites the instance buffer can hold at most.
+const MAX_SPRITE_COUNT: usize = (MAX_BINDING_SIZE / size_of::<SpriteInstance>() as u64) as usize;
+
+/// (56bytes)The per-instance data of a sprite.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SpriteInstance {
+    transform2d: Transform2D,
+    /// (min_u, min_v, max_u, max_v)
+    uv_rect: [f32; 4],
+    pivot: [f32; 2],
+    color: Hex,
+    _padding: u32,
+}
+
+unsafe impl bytemuck::Zeroable for SpriteInstance {}
+unsafe impl bytemuck::Pod for SpriteInstance {}
+
+impl SpriteInstance {
+    fn new(transform2d: &Transform2D, sprite: &Sprite) -> Self {
+        let uv = &sprite.uv_rect;
+
+        Self {
+            transform2d: *transform2d,
+            uv_rect: [uv.min.x, uv.min.y, uv.max.x, uv.max.y],
+            pivot: [sprite.pivot.x, sprite.pivot.y],
+            color: sprite.color.to_hex(),
+            _padding: 0,
+        }
+    }
+}
+
+/// A run of instances drawn with the same texture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Batch {
+    texture: Handle<Texture>,
+    range: Range<u32>,
+}
+
+/// The texture uploaded to the video card.
+struct GpuTexture {
+    #[allow(dead_code)]
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Renderer which renders `Sprite` as textured quads, batched by texture.
+pub(in super::super) struct SpriteRenderer {
+    /// The count of instances the instance buffer can hold, grow when a frame needs more.
+    capacity: usize,
+    instance_buf: wgpu::Buffer,
+
+    // Reused between frames to avoid allocations.
+    instances: Vec<SpriteInstance>,
+    batches: Vec<Batch>,
+
+    textures: HashMap<Handle<Texture>, GpuTexture>,
+    sampler: wgpu::Sampler,
+
+    common_bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl SpriteRenderer {
+    pub fn new(r2d: &Render2D) -> Self {
+        let Gpu {
+            device, sc_desc, ..
+        } = &r2d.gpu;
+
+        let capacity = INIT_SPRITE_COUNT;
+        let instance_buf = Self::create_instance_buffer(device, capacity);
+
+        let common_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sprite common bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let common_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sprite common bind group"),
+            layout: &common_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &r2d.utility_buf,
+                    offset: 0,
+                    size: None,
+                },
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sprite texture bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler {
+                            filtering: true,
+                            comparison: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("sprite sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sprite pipeline layout"),
+            bind_group_layouts: &[&common_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let vert_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("sprite vertex shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/sprite/sprite.vert.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        let frag_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("sprite fragment shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/sprite/sprite.frag.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sprite pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vert_shader,
+                entry_point: "main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<Vector4<f32>>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float4],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<SpriteInstance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            1 => Float2, 2 => Float2, 3 => Float2,
+                            4 => Float4, 5 => Float2, 6 => Uint
+                        ],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &frag_shader,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: sc_desc.format,
+                    color_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Max,
+                    },
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                ..Default::default()
+            },
+            // NOTE: Sprites are drawn in the order of batches, no depth test.
+            depth_stencil: None,
+            multisample: Default::default(),
+        });
+
+        Self {
+            capacity,
+            instance_buf,
+
+            instances: Vec::new(),
+            batches: Vec::new(),
+
+            textures: HashMap::new(),
+            sampler,
+
+            common_bind_group,
+            texture_bind_group_layout,
+            pipeline,
+        }
+    }
+
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sprite instance buffer"),
+            size: (capacity * size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Grow the instance buffer if it cannot hold `need` instances, but never beyond
+    /// `MAX_SPRITE_COUNT`.
+    fn reserve(&mut self, r2d: &Render2D, need: usize) {
+        if self.capacity >= need {
+            return;
+        }
+
+        self.capacity = need.next_power_of_two().min(MAX_SPRITE_COUNT);
+        self.instance_buf = Self::create_instance_buffer(&r2d.gpu.device, self.capacity);
+    }
+
+    /// Upload the textures used in this frame which are not on the video card yet.
+    fn prepare_textures(&mut self, r2d: &Render2D, textures: &Assets<Texture>) {
+        let Gpu { device, queue, .. } = &r2d.gpu;
+
+        // Drop the textures which have been removed from the `AssetServer`.
+        self.textures.retain(|handle, _| textures.contains(handle));
+
+        for batch in self.batches.iter() {
+            if self.textures.contains_key(&batch.texture) {
+                continue;
+            }
+
+            let texture = match textures.get(&batch.texture) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            let size = wgpu::Extent3d {
+                width: texture.width(),
+                height: texture.height(),
+                depth: 1,
+            };
+
+            let gpu_texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("sprite texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                // NOTE: The render targets are not sRGB, upload the texels unconverted like the
+                // colors of vertices, otherwise the sprites are too dark.
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            });
+
+            queue.write_texture(
+                wgpu::TextureCopyView {
+                    texture: &gpu_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                texture.data(),
+                wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: 4 * texture.width(),
+                    rows_per_image: texture.height(),
+                },
+                size,
+            );
+
+            let view = gpu_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("sprite texture bind group"),
+                layout: &self.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+
+            self.textures.insert(
+                batch.texture,
+                GpuTexture {
+                    texture: gpu_texture,
+                    bind_group,
+                },
+            );
+        }
+    }
+
+    /// Return the count of sprites dropped because they exceed `MAX_SPRITE_COUNT`.
+    pub fn render(&mut self, r2d: &Render2D, world: &World, resources: &Resources) -> usize {
+        let Gpu { device, queue, .. } = &r2d.gpu;
+
+        let Viewport {
+            x,
+            y,
+            w,
+            h,
+            min_depth,
+            max_depth,
+        } = r2d.viewport;
+
+        let frame_view = r2d.gpu.frame_view().expect("ERR: Not call begin_draw.");
+
+        Self::collect(world, &mut self.instances, &mut self.batches);
+
+        // Nothing to draw.
+        if self.instances.is_empty() {
+            return 0;
+        }
+
+        match resources.get::<AssetServer>() {
+            Some(asset_server) => self.prepare_textures(r2d, asset_server.textures()),
+            // NOTE: No texture can be found without `AssetServer`.
+            None => return 0,
+        }
+
+        // NOTE: The sprites beyond the limit of the video card are dropped.
+        let count = self.instances.len().min(MAX_SPRITE_COUNT);
+
+        self.reserve(r2d, count);
+        queue.write_buffer(
+            &self.instance_buf,
+            0,
+            bytemuck::cast_slice(&self.instances[..count]),
+        );
+
+        let i_buf_size = (count * size_of::<SpriteInstance>()) as wgpu::BufferAddress;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("sprite encoder"),
+        });
+
+        encoder.insert_debug_marker("render sprite");
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("sprite render pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: frame_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            rpass.push_debug_group("Set datas");
+
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_viewport(x, y, w, h, min_depth, max_depth);
+            rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
+            rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
+            rpass.set_vertex_buffer(1, self.instance_buf.slice(0..i_buf_size));
+            rpass.set_bind_group(0, &self.common_bind_group, &[]);
+
+            rpass.pop_debug_group();
+
+            for batch in self.batches.iter() {
+                // Skip the sprites dropped by the limit.
+                let range = batch.range.start..batch.range.end.min(count as u32);
+                if range.is_empty() {
+                    continue;
+                }
+
+                // Skip the sprites whose texture is not loaded.
+                if let Some(texture) = self.textures.get(&batch.texture) {
+                    rpass.set_bind_group(1, &texture.bind_group, &[]);
+                    rpass.draw_indexed(0..6, 0, range);
+                }
+            }
+        }
+
+        queue.submit(Some(encoder.finish()));
+
+        self.instances.len() - count
+    }
+
+    /// Collect the instances of `Sprite` from `World`, sorted by texture, and split them into batches.
+    fn collect(world: &World, instances: &mut Vec<SpriteInstance>, batches: &mut Vec<Batch>) {
+        let mut pairs: Vec<(Handle<Texture>, SpriteInstance)> = Vec::with_capacity(instances.len());
+
+        let mut q01 = <(&Transform2D, &Sprite)>::query();
+        let mut q02 = <(&Instance<Transform2D>, &Sprite)>::query();
+
+        q01.for_each(world, |(t, s)| {
+            pairs.push((s.texture, SpriteInstance::new(t, s)));
+        });
+
+        q02.for_each(world, |(ts, s)| {
+            pairs.extend(ts.iter().map(|t| (s.texture, SpriteInstance::new(t, s))));
+        });
+
+        // NOTE: Stable sort keeps the order of sprites with the same texture.
+        pairs.sort_by_key(|(texture, _)| texture.id());
+
+        instances.clear();
+        batches.clear();
+
+        for (index, (texture, instance)) in pairs.into_iter().enumerate() {
+            let index = index as u32;
+
+            match batches.last_mut() {
+                Some(batch) if batch.texture == texture => batch.range.end = index + 1,
+                _ => batches.push(Batch {
+                    texture,
+                    range: index..index + 1,
+                }),
+            }
+
+            instances.push(instance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect() {
+        let mut textures: Assets<Texture> = Assets::new();
+        let red = textures.add(Texture::from_color(1, 1, crate::Rgba::RED));
+        let blue = textures.add(Texture::from_color(1, 1, crate::Rgba::BLUE));
+
+        let mut world = World::default();
+
+        world.push((Transform2D::default(), Sprite::new(blue)));
+        world.push((Transform2D::default(), Sprite::new(red)));
+        world.push((vec![Transform2D::default(); 3], Sprite::new(blue)));
+
+        let mut instances = Vec::new();
+        let mut batches = Vec::new();
+
+        SpriteRenderer::collect(&world, &mut instances, &mut batches);
+
+        assert_eq!(instances.len(), 5);
+        assert_eq!(
+            batches,
+            vec![
+                Batch {
+                    texture: red,
+                    range: 0..1
+                },
+                Batch {
+                    texture: blue,
+                    range: 1..5
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,641 @@
+use crate::{
+    app::{AppSettings, AppStage},
+    events::Events,
+    legion::{
+        systems::{Builder, ParallelRunnable, Runnable},
+        Resources, Schedule, World,
+    },
+};
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt, mem,
+    rc::Rc,
+};
+
+/// The errors may occur when requesting a transition of `AppState`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AppStateError<'a> {
+    StateNotExist(&'a str),
+    /// The state is already in the stack, a state can't be entered twice.
+    StateInStack(&'a str),
+    /// There's no state to pop.
+    EmptyStack,
+}
+
+/// The transitions of `AppState`, published as `Events<StateEvent>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateEvent {
+    Entered(String),
+    Exited(String),
+    /// Another state is pushed over the state.
+    Paused(String),
+    /// The state over the state is popped.
+    Resumed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Transition {
+    Push(String),
+    Pop,
+    Replace(String),
+}
+
+/// The stack of the states, like menu, gameplay and pause.
+///
+/// Only the state on the top of the stack updates and its stages work, the states below it
+/// are paused. The transitions are applied at the start of next frame, after `AppSettings`.
+///
+/// NOTE: The stages of a state run `startup` when the state is entered, and `destroy` when it
+/// exits; pausing and resuming the state only make the stages rest and work.
+#[derive(Debug)]
+pub struct AppState {
+    names: HashSet<String>,
+    stack: Vec<String>,
+    transitions: Vec<Transition>,
+}
+
+impl AppState {
+    pub(crate) fn new(names: HashSet<String>, initial_state: Option<String>) -> Self {
+        Self {
+            names,
+            stack: Default::default(),
+            transitions: initial_state.into_iter().map(Transition::Push).collect(),
+        }
+    }
+
+    /// Return the state on the top of the stack.
+    pub fn current(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+
+    /// Return the states from the bottom to the top.
+    pub fn stack(&self) -> &[String] {
+        &self.stack
+    }
+
+    pub fn has_state(&self, state_name: &str) -> bool {
+        self.names.contains(state_name)
+    }
+
+    /// Pause the current state, and enter the state.
+    pub fn push<'a>(&mut self, state_name: &'a str) -> Result<(), AppStateError<'a>> {
+        if !self.has_state(state_name) {
+            Err(AppStateError::StateNotExist(state_name))
+        } else if self.projected_stack().contains(&state_name) {
+            Err(AppStateError::StateInStack(state_name))
+        } else {
+            self.transitions
+                .push(Transition::Push(String::from(state_name)));
+
+            Ok(())
+        }
+    }
+
+    /// Exit the current state, and resume the state below it.
+    pub fn pop<'a>(&mut self) -> Result<(), AppStateError<'a>> {
+        if self.projected_stack().is_empty() {
+            Err(AppStateError::EmptyStack)
+        } else {
+            self.transitions.push(Transition::Pop);
+
+            Ok(())
+        }
+    }
+
+    /// Exit the current state, and enter the state.
+    ///
+    /// Replacing the current state with itself restarts it.
+    pub fn replace<'a>(&mut self, state_name: &'a str) -> Result<(), AppStateError<'a>> {
+        let stack = self.projected_stack();
+
+        if !self.has_state(state_name) {
+            Err(AppStateError::StateNotExist(state_name))
+        } else if stack[..stack.len().saturating_sub(1)].contains(&state_name) {
+            Err(AppStateError::StateInStack(state_name))
+        } else {
+            self.transitions
+                .push(Transition::Replace(String::from(state_name)));
+
+            Ok(())
+        }
+    }
+
+    /// The stack after the transitions requested in this frame are applied.
+    fn projected_stack(&self) -> Vec<&str> {
+        let mut stack: Vec<&str> = self.stack.iter().map(String::as_str).collect();
+
+        for transition in self.transitions.iter() {
+            match transition {
+                Transition::Push(name) => stack.push(name),
+                Transition::Pop => {
+                    stack.pop();
+                }
+                Transition::Replace(name) => {
+                    stack.pop();
+                    stack.push(name);
+                }
+            }
+        }
+
+        stack
+    }
+}
+
+/// Build a state of `AppState`, which is added by `AppBuilder::add_state()`.
+pub struct AppStateBuilder {
+    name: String,
+    stages: Vec<String>,
+
+    builder_enter: Builder,
+    builder_update: Builder,
+    builder_exit: Builder,
+}
+
+impl fmt::Debug for AppStateBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppStateBuilder")
+            .field("name", &self.name)
+            .field("stages", &self.stages)
+            .finish()
+    }
+}
+
+impl AppStateBuilder {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            stages: Default::default(),
+
+            builder_enter: Builder::default(),
+            builder_update: Builder::default(),
+            builder_exit: Builder::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Return the names of the stages which work only in this state.
+    pub fn stages(&self) -> &[String] {
+        &self.stages
+    }
+
+    /// The stage rests until the state is entered, a stage can belong to only one state.
+    pub fn with_stage(mut self, stage_name: &str) -> Self {
+        self.stages.push(String::from(stage_name));
+
+        self
+    }
+
+    pub fn add_system_enter<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
+        self.builder_enter.add_system(system);
+
+        self
+    }
+
+    pub fn add_system_update<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
+        self.builder_update.add_system(system);
+
+        self
+    }
+
+    pub fn add_system_exit<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
+        self.builder_exit.add_system(system);
+
+        self
+    }
+
+    pub fn add_thread_local_system_enter<T: Runnable + 'static>(mut self, system: T) -> Self {
+        self.builder_enter.add_thread_local(system);
+
+        self
+    }
+
+    pub fn add_thread_local_system_update<T: Runnable + 'static>(mut self, system: T) -> Self {
+        self.builder_update.add_thread_local(system);
+
+        self
+    }
+
+    pub fn add_thread_local_system_exit<T: Runnable + 'static>(mut self, system: T) -> Self {
+        self.builder_exit.add_thread_local(system);
+
+        self
+    }
+
+    pub fn add_thread_local_fn_enter<F: FnMut(&mut World, &mut Resources) + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.builder_enter.add_thread_local_fn(f);
+
+        self
+    }
+
+    pub fn add_thread_local_fn_update<F: FnMut(&mut World, &mut Resources) + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.builder_update.add_thread_local_fn(f);
+
+        self
+    }
+
+    pub fn add_thread_local_fn_exit<F: FnMut(&mut World, &mut Resources) + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.builder_exit.add_thread_local_fn(f);
+
+        self
+    }
+
+    pub(crate) fn build(mut self) -> State {
+        State {
+            name: self.name,
+            stages: self.stages,
+
+            on_enter: self.builder_enter.build(),
+            on_update: self.builder_update.build(),
+            on_exit: self.builder_exit.build(),
+        }
+    }
+}
+
+pub(crate) struct State {
+    name: String,
+    stages: Vec<String>,
+
+    on_enter: Schedule,
+    on_update: Schedule,
+    on_exit: Schedule,
+}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("name", &self.name)
+            .field("stages", &self.stages)
+            .finish()
+    }
+}
+
+/// Run the schedules of the states and apply the transitions requested by `AppState`.
+///
+/// NOTE: The schedules are kept out of `Resources`, so they can access `AppState`.
+pub(crate) struct StateMachine {
+    states: HashMap<String, State>,
+}
+
+impl StateMachine {
+    /// NOTE: The stages of the states rest until the states are entered.
+    pub(crate) fn new(states: Vec<State>, settings: &mut AppSettings) -> Self {
+        for stage_name in states.iter().flat_map(|state| state.stages.iter()) {
+            settings.rest_stage_now(stage_name);
+        }
+
+        Self {
+            states: states
+                .into_iter()
+                .map(|state| (state.name.clone(), state))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn names(&self) -> HashSet<String> {
+        self.states.keys().cloned().collect()
+    }
+
+    /// Apply the transitions requested in last frame.
+    pub(crate) fn apply(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        busy_stages: &Rc<RefCell<Vec<AppStage>>>,
+    ) {
+        let transitions = match resources.get_mut::<AppState>() {
+            Some(mut app_state) => mem::take(&mut app_state.transitions),
+            None => return,
+        };
+
+        for transition in transitions {
+            match transition {
+                Transition::Push(name) => {
+                    if let Some(top) = Self::current(resources) {
+                        self.pause(&top, resources);
+                    }
+
+                    self.enter(&name, world, resources, busy_stages);
+                }
+                Transition::Pop => {
+                    if let Some(top) = Self::current(resources) {
+                        self.exit(&top, world, resources, busy_stages);
+                    }
+
+                    if let Some(top) = Self::current(resources) {
+                        self.resume(&top, resources, busy_stages);
+                    }
+                }
+                Transition::Replace(name) => {
+                    if let Some(top) = Self::current(resources) {
+                        self.exit(&top, world, resources, busy_stages);
+                    }
+
+                    self.enter(&name, world, resources, busy_stages);
+                }
+            }
+        }
+    }
+
+    /// Run `on_update` of the current state.
+    pub(crate) fn update(&mut self, world: &mut World, resources: &mut Resources) {
+        if let Some(top) = Self::current(resources) {
+            self.state_mut(&top).on_update.execute(world, resources);
+        }
+    }
+
+    /// Exit all states from the top to the bottom, when the app is going to quit.
+    pub(crate) fn exit_all(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        busy_stages: &Rc<RefCell<Vec<AppStage>>>,
+    ) {
+        while let Some(top) = Self::current(resources) {
+            self.exit(&top, world, resources, busy_stages);
+        }
+    }
+
+    fn current(resources: &Resources) -> Option<String> {
+        resources
+            .get::<AppState>()
+            .and_then(|app_state| app_state.current().map(String::from))
+    }
+
+    fn publish(resources: &Resources, event: StateEvent) {
+        if let Some(mut events) = resources.get_mut::<Events<StateEvent>>() {
+            events.send(event);
+        }
+    }
+
+    fn state_mut(&mut self, name: &str) -> &mut State {
+        self.states.get_mut(name).expect("ERR: Not find the state.")
+    }
+
+    fn enter(
+        &mut self,
+        name: &str,
+        world: &mut World,
+        resources: &mut Resources,
+        busy_stages: &Rc<RefCell<Vec<AppStage>>>,
+    ) {
+        resources
+            .get_mut::<AppState>()
+            .unwrap()
+            .stack
+            .push(String::from(name));
+
+        let state = self.state_mut(name);
+        state.on_enter.execute(world, resources);
+
+        for stage_name in state.stages.iter() {
+            resources
+                .get_mut::<AppSettings>()
+                .unwrap()
+                .work_stage_now(stage_name);
+
+            if let Some(stage) = busy_stages
+                .borrow()
+                .iter()
+                .find(|stage| stage.name() == stage_name)
+            {
+                stage.init(world, resources);
+            }
+        }
+
+        Self::publish(resources, StateEvent::Entered(String::from(name)));
+    }
+
+    fn exit(
+        &mut self,
+        name: &str,
+        world: &mut World,
+        resources: &mut Resources,
+        busy_stages: &Rc<RefCell<Vec<AppStage>>>,
+    ) {
+        let state = self.state_mut(name);
+
+        for stage_name in state.stages.iter() {
+            if let Some(stage) = busy_stages
+                .borrow()
+                .iter()
+                .find(|stage| stage.name() == stage_name)
+            {
+                stage.free(world, resources);
+            }
+
+            // NOTE: The state is paused, its stages rest already.
+            let stage = resources
+                .get_mut::<AppSettings>()
+                .unwrap()
+                .take_spare_stage(stage_name);
+            if let Some(stage) = stage {
+                stage.free(world, resources);
+
+                resources
+                    .get_mut::<AppSettings>()
+                    .unwrap()
+                    .push_stage_to_rest(stage)
+                    .expect("ERR: Failed to make the stage rest.");
+            } else {
+                resources
+                    .get_mut::<AppSettings>()
+                    .unwrap()
+                    .rest_stage_now(stage_name);
+            }
+        }
+
+        state.on_exit.execute(world, resources);
+
+        resources.get_mut::<AppState>().unwrap().stack.pop();
+
+        Self::publish(resources, StateEvent::Exited(String::from(name)));
+    }
+
+    fn pause(&mut self, name: &str, resources: &mut Resources) {
+        let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+        for stage_name in self.state_mut(name).stages.iter() {
+            settings.rest_stage_now(stage_name);
+        }
+
+        Self::publish(resources, StateEvent::Paused(String::from(name)));
+    }
+
+    fn resume(
+        &mut self,
+        name: &str,
+        resources: &mut Resources,
+        busy_stages: &Rc<RefCell<Vec<AppStage>>>,
+    ) {
+        let mut settings = resources.get_mut::<AppSettings>().unwrap();
+
+        for stage_name in self.state_mut(name).stages.iter() {
+            settings.work_stage_now(stage_name);
+
+            // NOTE: The time while resting isn't counted.
+            if let Some(stage) = busy_stages
+                .borrow()
+                .iter()
+                .find(|stage| stage.name() == stage_name)
+            {
+                stage.skip_time();
+            }
+        }
+
+        Self::publish(resources, StateEvent::Resumed(String::from(name)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{AppBuilder, AppStageBuilder, HeadlessMode},
+        events::EventReader,
+    };
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    fn log(resources: &mut Resources, msg: &str) {
+        resources
+            .get_mut_or_default::<Log>()
+            .0
+            .push(String::from(msg));
+    }
+
+    fn logged_stage(name: &'static str) -> AppStageBuilder {
+        AppStageBuilder::new(String::from(name))
+            .add_thread_local_fn_startup(move |_, resources| {
+                log(resources, &format!("{}.startup", name))
+            })
+            .add_thread_local_fn_process(move |_, resources| {
+                log(resources, &format!("{}.process", name))
+            })
+            .add_thread_local_fn_destroy(move |_, resources| {
+                log(resources, &format!("{}.destroy", name))
+            })
+    }
+
+    fn logged_state(name: &'static str) -> AppStateBuilder {
+        AppStateBuilder::new(String::from(name))
+            .add_thread_local_fn_enter(move |_, resources| {
+                log(resources, &format!("{}.enter", name))
+            })
+            .add_thread_local_fn_update(move |_, resources| {
+                log(resources, &format!("{}.update", name))
+            })
+            .add_thread_local_fn_exit(move |_, resources| log(resources, &format!("{}.exit", name)))
+    }
+
+    #[test]
+    fn test_app_state_transitions() {
+        let mut frame = 0;
+
+        let driver = AppStageBuilder::new(String::from("driver"))
+            .add_thread_local_fn_process(move |_, resources| {
+                frame += 1;
+
+                let mut app_state = resources.get_mut::<AppState>().unwrap();
+                match frame {
+                    1 => {
+                        assert_eq!(app_state.current(), Some("menu"));
+                        assert_eq!(
+                            app_state.push("menu"),
+                            Err(AppStateError::StateInStack("menu"))
+                        );
+                        assert_eq!(
+                            app_state.push("missing"),
+                            Err(AppStateError::StateNotExist("missing"))
+                        );
+
+                        app_state.replace("game").unwrap();
+                    }
+                    2 => {
+                        app_state.push("pause").unwrap();
+                    }
+                    3 => {
+                        assert_eq!(app_state.stack(), ["game", "pause"]);
+
+                        // the events of frame 2 and 3.
+                        let events = resources.get::<Events<StateEvent>>().unwrap();
+                        assert_eq!(
+                            EventReader::default().iter(&events).collect::<Vec<_>>(),
+                            [
+                                &StateEvent::Exited(String::from("menu")),
+                                &StateEvent::Entered(String::from("game")),
+                                &StateEvent::Paused(String::from("game")),
+                                &StateEvent::Entered(String::from("pause")),
+                            ]
+                        );
+
+                        app_state.pop().unwrap();
+                    }
+                    _ => resources.get_mut::<AppSettings>().unwrap().quit(),
+                }
+            })
+            .add_thread_local_fn_destroy(|_, resources| {
+                let log = resources.get::<Log>().unwrap();
+
+                assert_eq!(
+                    log.0,
+                    [
+                        // frame 1
+                        "menu.enter",
+                        "menu_stage.startup",
+                        "menu.update",
+                        "menu_stage.process",
+                        // frame 2
+                        "menu_stage.destroy",
+                        "menu.exit",
+                        "game.enter",
+                        "game_stage.startup",
+                        "game.update",
+                        "game_stage.process",
+                        // frame 3
+                        "pause.enter",
+                        "pause.update",
+                        // frame 4
+                        "pause.exit",
+                        "game.update",
+                        "game_stage.process",
+                        // quit
+                        "game_stage.destroy",
+                        "game.exit",
+                    ]
+                );
+            });
+
+        AppBuilder::new()
+            .add_stage_builder(driver)
+            .unwrap()
+            .add_stage_builder(logged_stage("menu_stage"))
+            .unwrap()
+            .add_stage_builder(logged_stage("game_stage"))
+            .unwrap()
+            .add_state(logged_state("menu").with_stage("menu_stage"))
+            .unwrap()
+            .add_state(logged_state("game").with_stage("game_stage"))
+            .unwrap()
+            .add_state(logged_state("pause"))
+            .unwrap()
+            .with_initial_state("menu")
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::Frames(8));
+    }
+}
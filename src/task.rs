@@ -0,0 +1,287 @@
+use crate::{
+    app::{AppBuildError, AppBuilder},
+    legion::{systems::CommandBuffer, Resources, World},
+    plugin::Plugin,
+};
+
+use futures::{
+    executor::{block_on, ThreadPool},
+    future::RemoteHandle,
+    FutureExt,
+};
+use std::{fmt, future::Future, sync::Mutex};
+
+/// The command to write the output of a task to `World`.
+type TaskCommand = Box<dyn FnOnce(&mut CommandBuffer) + Send>;
+
+/// The resource to run the slow works, like loading files or pathfinding, on the background
+/// threads without blocking the frame loop.
+///
+/// ```
+/// use yam::TaskPool;
+///
+/// let pool = TaskPool::with_threads(1);
+/// let task = pool.spawn_job(|| (0..100u32).sum::<u32>());
+///
+/// // NOTE: In a system, keep the task and `Task::poll()` it every frame instead.
+/// assert_eq!(task.block_on(), Some(4950));
+/// ```
+pub struct TaskPool {
+    pool: ThreadPool,
+    // the tasks which write their outputs to `World` when they're finished.
+    commands: Mutex<Vec<Task<TaskCommand>>>,
+}
+
+impl Default for TaskPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskPool {
+    /// Create the pool with a thread per core, except the one of the main thread.
+    pub fn new() -> Self {
+        Self::with_threads(num_cpus::get().saturating_sub(1).max(1))
+    }
+
+    pub fn with_threads(threads: usize) -> Self {
+        let pool = ThreadPool::builder()
+            .pool_size(threads.max(1))
+            .name_prefix("yam-task-")
+            .create()
+            .expect("ERR: Failed to create the thread pool.");
+
+        Self {
+            pool,
+            commands: Default::default(),
+        }
+    }
+
+    /// Run the future on the pool, the returned `Task` polls its output.
+    pub fn spawn<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (remote, handle) = future.remote_handle();
+        self.pool.spawn_ok(remote);
+
+        Task {
+            handle: Some(handle),
+        }
+    }
+
+    /// Run the CPU-heavy job on the pool, e.g. generating a level.
+    pub fn spawn_job<T, F>(&self, job: F) -> Task<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.spawn(async move { job() })
+    }
+
+    /// Run the future on the pool, and write its output to `World` by the `CommandBuffer` at the
+    /// start of the frame after it's finished.
+    ///
+    /// ```
+    /// use yam::{legion::systems::CommandBuffer, TaskPool};
+    ///
+    /// struct Level(Vec<u8>);
+    ///
+    /// let pool = TaskPool::new();
+    /// pool.spawn_command(async {
+    ///     let level = Level(vec![0; 64]);
+    ///
+    ///     move |cmd: &mut CommandBuffer| {
+    ///         cmd.push((level,));
+    ///     }
+    /// });
+    /// ```
+    pub fn spawn_command<F, C>(&self, future: F)
+    where
+        F: Future<Output = C> + Send + 'static,
+        C: FnOnce(&mut CommandBuffer) + Send + 'static,
+    {
+        let task = self.spawn(future.map(|command| Box::new(command) as TaskCommand));
+
+        self.commands
+            .lock()
+            .expect("ERR: The task pool is poisoned.")
+            .push(task);
+    }
+
+    /// Return the number of the tasks spawned by `Self::spawn_command()` which aren't finished.
+    pub fn pending_commands(&self) -> usize {
+        self.commands
+            .lock()
+            .expect("ERR: The task pool is poisoned.")
+            .len()
+    }
+
+    /// Write the outputs of the finished tasks to `World`, called once per frame by the `App`.
+    pub(crate) fn flush(world: &mut World, resources: &mut Resources) {
+        let mut cmd = CommandBuffer::new(world);
+
+        if let Some(pool) = resources.get::<TaskPool>() {
+            let mut commands = pool
+                .commands
+                .lock()
+                .expect("ERR: The task pool is poisoned.");
+
+            // NOTE: The commands are written in the order the tasks finish, not spawn.
+            let mut pending = Vec::with_capacity(commands.len());
+            for mut task in commands.drain(..) {
+                match task.poll() {
+                    Some(command) => command(&mut cmd),
+                    None => pending.push(task),
+                }
+            }
+            *commands = pending;
+        }
+
+        cmd.flush(world, resources);
+    }
+}
+
+impl fmt::Debug for TaskPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskPool")
+            .field("pool", &self.pool)
+            .field("pending_commands", &self.pending_commands())
+            .finish()
+    }
+}
+
+/// The handle of the future or job running on `TaskPool`.
+///
+/// Dropping the `Task` cancels the future, call `Self::detach()` to let it run to the end.
+///
+/// NOTE: If the future panics, the panic resumes in the thread polling the `Task`.
+#[derive(Debug)]
+pub struct Task<T> {
+    // `None` if the output has been taken.
+    handle: Option<RemoteHandle<T>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// Take the output if the task is finished, without blocking.
+    ///
+    /// Return `None` if the task is running, or the output has been taken.
+    pub fn poll(&mut self) -> Option<T> {
+        let output = self.handle.as_mut()?.now_or_never();
+
+        if output.is_some() {
+            self.handle = None;
+        }
+
+        output
+    }
+
+    /// Block the current thread until the task is finished, and return the output.
+    pub fn block_on(mut self) -> Option<T> {
+        self.handle.take().map(block_on)
+    }
+
+    /// Let the task run to the end without the output.
+    pub fn detach(mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.forget();
+        }
+    }
+}
+
+/// The default plugin of `TaskPool`.
+pub struct TaskPlugin;
+
+impl Plugin for TaskPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_resource(TaskPool::new());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{AppBuilder, AppSettings, HeadlessMode},
+        legion::IntoQuery,
+    };
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    /// The tests fail instead of hanging if the tasks never finish.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_task_pool() {
+        let pool = TaskPool::with_threads(2);
+
+        let mut task = pool.spawn(async { 1 + 1 });
+        let deadline = Instant::now() + TIMEOUT;
+        let output = loop {
+            if let Some(output) = task.poll() {
+                break output;
+            }
+            assert!(Instant::now() < deadline, "ERR: The task never finishes.");
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(output, 2);
+        assert_eq!(task.poll(), None);
+
+        assert_eq!(pool.spawn_job(|| "job").block_on(), Some("job"));
+
+        let (tx, rx) = mpsc::channel();
+        pool.spawn_job(move || tx.send("detached").unwrap())
+            .detach();
+        assert_eq!(rx.recv_timeout(TIMEOUT), Ok("detached"));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Level(u32);
+
+    #[test]
+    fn test_task_commands() {
+        let deadline = Instant::now() + TIMEOUT;
+
+        AppBuilder::new()
+            .create_stage_builder(String::from("level"))
+            .unwrap()
+            .add_thread_local_fn_startup(|_, resources| {
+                let pool = resources.get::<TaskPool>().unwrap();
+
+                pool.spawn_command(async {
+                    let level = Level(7);
+
+                    move |cmd: &mut CommandBuffer| {
+                        cmd.push((level,));
+                    }
+                });
+            })
+            .add_thread_local_fn_process(move |world, resources| {
+                if <&Level>::query().iter(world).next().is_some() {
+                    assert_eq!(resources.get::<TaskPool>().unwrap().pending_commands(), 0);
+
+                    resources.get_mut::<AppSettings>().unwrap().quit();
+                } else {
+                    assert!(
+                        Instant::now() < deadline,
+                        "ERR: The command is never applied."
+                    );
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+            .add_thread_local_fn_destroy(|world, _| {
+                let levels: Vec<&Level> = <&Level>::query().iter(world).collect();
+                assert_eq!(levels, [&Level(7)]);
+            })
+            .into_app_builder()
+            .build()
+            .unwrap()
+            .run_headless(HeadlessMode::UntilQuit);
+    }
+}
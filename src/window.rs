@@ -1,25 +1,249 @@
 //! NOTE: The `window` module is only in a usable state and will be gradually improved afterwards.
 
+use crate::{
+    app::{AppBuildError, AppBuilder},
+    asset::{AssetServer, Handle, Texture},
+    input::{Input, InputEvent},
+    misc::{
+        coordinates::Transformation,
+        json::{self, JsonError},
+    },
+    plugin::Plugin,
+    DEFAULT_HEIGHT, DEFAULT_WIDTH,
+};
+
+use serde::{Deserialize, Serialize};
+
 use winit::{
-    dpi::{PhysicalPosition, PhysicalSize},
-    window::Window as Window_w,
+    dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
+    event_loop::EventLoopWindowTarget,
+    window::{Icon, Window as Window_w, WindowBuilder},
 };
 
+use std::{collections::HashMap, error::Error, fmt, path::Path};
+
 pub type VideoMode = winit::monitor::VideoMode;
 pub type MonitorHandle = winit::monitor::MonitorHandle;
 pub type Fullscreen = winit::window::Fullscreen;
 pub type NotSupportError = winit::error::NotSupportedError;
 pub type ExternalError = winit::error::ExternalError;
+pub type CursorIcon = winit::window::CursorIcon;
+pub type WindowId = winit::window::WindowId;
+
+/// The window is resized, the size is in physical pixels.
+///
+/// It's published as `Events<WindowResized>` for the primary and extra windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowResized {
+    pub id: WindowId,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The errors may occur when setting the icon of window.
+#[derive(Debug)]
+pub enum IconError {
+    /// The texture is not stored in `AssetServer`.
+    TextureNotFound,
+    /// The texture cannot be used as an icon.
+    BadIcon(winit::window::BadIcon),
+}
+
+impl fmt::Display for IconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IconError::TextureNotFound => write!(f, "texture not found in asset server"),
+            IconError::BadIcon(err) => write!(f, "bad icon: {}", err),
+        }
+    }
+}
+
+impl Error for IconError {}
+
+/// How the frames are presented to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentMode {
+    /// Present immediately without vsync, the tearing may be visible.
+    Immediate,
+    /// Present the latest frame at the vertical blank, the frame rate isn't limited.
+    Mailbox,
+    /// Present with vsync, the frame rate is limited by the refresh rate of the monitor.
+    Fifo,
+}
+
+impl PresentMode {
+    pub(crate) fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+        }
+    }
+}
+
+/// The fullscreen mode of the window when it's created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowMode {
+    Windowed,
+    /// Fullscreen without changing the video mode of the primary monitor.
+    BorderlessFullscreen,
+    /// Fullscreen with the video mode which has the largest size and refresh rate of the
+    /// primary monitor.
+    ExclusiveFullscreen,
+}
+
+/// The settings to create the window, the sizes and the position are in logical pixels.
+///
+/// It can be loaded from a json file, the missing fields take the default values:
+///
+/// ```
+/// use yam::{PresentMode, WindowDescriptor};
+///
+/// let desc = WindowDescriptor::from_json(r#"{ "title": "yam", "present_mode": "Fifo" }"#).unwrap();
+///
+/// assert_eq!(desc.title, "yam");
+/// assert_eq!(desc.present_mode, PresentMode::Fifo);
+/// assert!(desc.resizable);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowDescriptor {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    /// The left-top position of the window, `None` means decided by the system.
+    pub position: Option<(i32, i32)>,
+    pub mode: WindowMode,
+    pub present_mode: PresentMode,
+    pub resizable: bool,
+    pub decorations: bool,
+    /// NOTE: The render always draws opaque background now, so it only has effect on the
+    /// area the render doesn't touch.
+    pub transparent: bool,
+}
+
+impl Default for WindowDescriptor {
+    fn default() -> Self {
+        Self {
+            title: String::from("default"),
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            min_size: None,
+            max_size: None,
+            position: None,
+            mode: WindowMode::Windowed,
+            // NOTE: Keep the same as before, the frame rate isn't limited by vsync.
+            present_mode: PresentMode::Mailbox,
+            resizable: true,
+            decorations: true,
+            transparent: false,
+        }
+    }
+}
+
+impl WindowDescriptor {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Load the settings from a json file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, JsonError> {
+        json::load(path.as_ref())
+    }
+
+    /// Save the settings to a json file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), JsonError> {
+        json::save(path.as_ref(), &self.to_json()?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        json::from_json(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, JsonError> {
+        json::to_json(self, true)
+    }
+
+    fn fullscreen<T>(&self, target: &EventLoopWindowTarget<T>) -> Option<Fullscreen> {
+        match self.mode {
+            WindowMode::Windowed => None,
+            WindowMode::BorderlessFullscreen => {
+                Some(Fullscreen::Borderless(target.primary_monitor()))
+            }
+            WindowMode::ExclusiveFullscreen => target
+                .primary_monitor()
+                .and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|mode| {
+                        let size = mode.size();
+                        (size.width * size.height, mode.refresh_rate())
+                    })
+                })
+                .map(Fullscreen::Exclusive),
+        }
+    }
+}
 
 /// `Window` is a simple wrapper for `winit::window::Window`, which cuts out many functions of
 /// `winit::window::Window`, only keeps what `yam` cares about.
 pub struct Window {
     pub(crate) window: Window_w,
+    hidpi_scaling: bool,
+    present_mode: PresentMode,
 }
 
 impl Window {
-    pub(crate) fn new(window: Window_w) -> Self {
-        Self { window }
+    /// Create the window by the descriptor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system fails to create the window.
+    pub(crate) fn new<T>(desc: &WindowDescriptor, target: &EventLoopWindowTarget<T>) -> Self {
+        let mut builder = WindowBuilder::new()
+            .with_title(desc.title.as_str())
+            .with_inner_size(LogicalSize::new(desc.width, desc.height))
+            .with_resizable(desc.resizable)
+            .with_decorations(desc.decorations)
+            .with_transparent(desc.transparent)
+            .with_fullscreen(desc.fullscreen(target));
+
+        if let Some((width, height)) = desc.min_size {
+            builder = builder.with_min_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((width, height)) = desc.max_size {
+            builder = builder.with_max_inner_size(LogicalSize::new(width, height));
+        }
+
+        let window = builder
+            .build(target)
+            .expect("ERR: Failed to create the window.");
+
+        // NOTE: `WindowBuilder` can't set the position in this version of winit.
+        if let Some((x, y)) = desc.position {
+            window.set_outer_position(LogicalPosition::new(x, y));
+        }
+
+        Self {
+            window,
+            hidpi_scaling: false,
+            present_mode: desc.present_mode,
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// Return how the frames are presented to the window.
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Modifies how the frames are presented to the window, the swap chain is recreated in
+    /// next frame.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode;
     }
 
     pub fn available_monitors(&self) -> impl Iterator<Item = MonitorHandle> {
@@ -42,18 +266,63 @@ impl Window {
         self.window.set_fullscreen(fullscreen);
     }
 
-    /// Return the resolution of the window.
+    /// Return the resolution of the window in physical pixels.
     pub fn resolution(&self) -> (u32, u32) {
         let size = self.window.inner_size();
 
         (size.width, size.height)
     }
 
-    /// Modifies the resolution of the window.
+    /// Modifies the resolution of the window in physical pixels.
     pub fn set_resolution(&mut self, width: u32, height: u32) {
         self.window.set_inner_size(PhysicalSize::new(width, height));
     }
 
+    /// Return the resolution of the window in logical pixels.
+    pub fn logical_resolution(&self) -> (f64, f64) {
+        let size = self
+            .window
+            .inner_size()
+            .to_logical::<f64>(self.scale_factor());
+
+        (size.width, size.height)
+    }
+
+    /// Modifies the resolution of the window in logical pixels, so the window keeps the same
+    /// size on the monitors with different scale factors.
+    pub fn set_logical_resolution(&mut self, width: f64, height: f64) {
+        self.window.set_inner_size(LogicalSize::new(width, height));
+    }
+
+    /// Return the ratio of physical pixels to logical pixels of the monitor the window is on.
+    ///
+    /// It changes when the window is moved to another monitor or the system settings change.
+    pub fn scale_factor(&self) -> f64 {
+        self.window.scale_factor()
+    }
+
+    /// Return whether the sizes in `screen space` are measured in logical pixels.
+    pub fn hidpi_scaling(&self) -> bool {
+        self.hidpi_scaling
+    }
+
+    /// Set whether the sizes in `screen space`(like `BorderThickness::ScreenSpace`) are measured
+    /// in logical pixels, so they keep the same visual size on HiDPI monitors.
+    ///
+    /// It's disabled by default, the sizes are in physical pixels.
+    pub fn set_hidpi_scaling(&mut self, hidpi_scaling: bool) {
+        self.hidpi_scaling = hidpi_scaling;
+    }
+
+    /// Return the physical pixels of one unit in `screen space`.
+    pub fn pixel_ratio(&self) -> f32 {
+        if self.hidpi_scaling {
+            self.scale_factor() as f32
+        } else {
+            1.0
+        }
+    }
+
     /// Return the the left-top position of the window.
     pub fn position(&self) -> Result<(i32, i32), NotSupportError> {
         let position = self.window.inner_position()?;
@@ -102,13 +371,235 @@ impl Window {
         self.window.set_cursor_visible(visible);
     }
 
-    // TODO: After finish texture module.
-    pub fn set_cursor_icon(&mut self) {
-        todo!()
+    /// Modifies the cursor icon of the window.
+    ///
+    /// NOTE: Only the system cursors are supported now, custom cursor images are not.
+    pub fn set_cursor_icon(&mut self, cursor: CursorIcon) {
+        self.window.set_cursor_icon(cursor);
+    }
+
+    /// Modifies the icon of the window, `None` means the default icon.
+    ///
+    /// It has no effect on macOS and wayland.
+    pub fn set_window_icon(
+        &mut self,
+        asset_server: &AssetServer,
+        icon: Option<Handle<Texture>>,
+    ) -> Result<(), IconError> {
+        let icon = match icon {
+            Some(handle) => {
+                let texture = asset_server
+                    .texture(&handle)
+                    .ok_or(IconError::TextureNotFound)?;
+
+                let icon =
+                    Icon::from_rgba(texture.data().to_vec(), texture.width(), texture.height())
+                        .map_err(IconError::BadIcon)?;
+
+                Some(icon)
+            }
+            None => None,
+        };
+
+        self.window.set_window_icon(icon);
+
+        Ok(())
+    }
+}
+
+/// The extra windows opened at runtime, like a minimap or an inspector.
+///
+/// The primary window is the `Window` resource, closing it quits the app, but closing an extra
+/// window only closes itself. Each extra window has its own `Input`, and a `Camera2D` with the
+/// `WindowTarget` component draws into it.
+///
+/// NOTE: The input of extra windows isn't recorded by `InputRecorder` or sent by
+/// `Events<InputEvent>`, and `DebugDraw` only draws into the primary window.
+pub struct Windows {
+    windows: HashMap<WindowId, Window>,
+    inputs: HashMap<WindowId, Input>,
+    transformations: HashMap<WindowId, Transformation>,
+    input_evts: HashMap<WindowId, Vec<InputEvent>>,
+
+    // the windows requested to open in this frame.
+    pending: Vec<WindowDescriptor>,
+    just_opened: Vec<WindowId>,
+    // the windows closed in this frame.
+    closed: Vec<(WindowId, Window)>,
+    just_closed: Vec<WindowId>,
+    // NOTE: The closed windows are dropped one frame later, after the render drops their surfaces.
+    dropping: Vec<Window>,
+}
+
+impl Windows {
+    pub(crate) fn new() -> Self {
+        Self {
+            windows: HashMap::with_capacity(2),
+            inputs: HashMap::with_capacity(2),
+            transformations: HashMap::with_capacity(2),
+            input_evts: HashMap::with_capacity(2),
+
+            pending: Vec::with_capacity(2),
+            just_opened: Vec::with_capacity(2),
+            closed: Vec::with_capacity(2),
+            just_closed: Vec::with_capacity(2),
+            dropping: Vec::with_capacity(2),
+        }
+    }
+
+    /// Request to open a window, it's opened at the end of this frame, then its id can be got
+    /// by `Self::just_opened()` in the next frame.
+    pub fn open(&mut self, desc: WindowDescriptor) {
+        self.pending.push(desc);
+    }
+
+    /// Close the window, return `false` if it's not opened.
+    pub fn close(&mut self, id: WindowId) -> bool {
+        self.inputs.remove(&id);
+        self.transformations.remove(&id);
+        self.input_evts.remove(&id);
+
+        match self.windows.remove(&id) {
+            Some(window) => {
+                window.window.set_visible(false);
+                self.closed.push((id, window));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&Window> {
+        self.windows.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut Window> {
+        self.windows.get_mut(&id)
+    }
+
+    /// Return the input of the window, it only receives the events sent to the window.
+    pub fn input(&self, id: WindowId) -> Option<&Input> {
+        self.inputs.get(&id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.windows.keys().copied()
     }
 
-    // TODO: After finish texture module.
-    pub fn set_window_icon(&mut self) {
-        todo!()
+    /// Return the windows opened at the end of last frame.
+    pub fn just_opened(&self) -> &[WindowId] {
+        &self.just_opened
+    }
+
+    /// Return the windows closed in last frame, by user or `Self::close()`.
+    pub fn just_closed(&self) -> &[WindowId] {
+        &self.just_closed
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub(crate) fn contains(&self, id: WindowId) -> bool {
+        self.windows.contains_key(&id)
+    }
+
+    pub(crate) fn push_input_event(&mut self, id: WindowId, evt: InputEvent) {
+        if let Some(evts) = self.input_evts.get_mut(&id) {
+            evts.push(evt);
+        }
+    }
+
+    /// Record how the render transforms the coordinates of the window.
+    pub(crate) fn set_transformation(&mut self, id: WindowId, trf: Transformation) {
+        if self.windows.contains_key(&id) {
+            self.transformations.insert(id, trf);
+        }
+    }
+
+    /// Apply the input events of all windows, then open the requested windows.
+    pub(crate) fn maintain<T>(&mut self, target: &EventLoopWindowTarget<T>) {
+        for (id, input) in self.inputs.iter_mut() {
+            let trf = self.transformations.get(id).copied().unwrap_or_default();
+            let evts = self
+                .input_evts
+                .get_mut(id)
+                .expect("ERR: window events lost.");
+
+            input.apply(evts, &trf);
+            evts.clear();
+        }
+
+        self.just_opened.clear();
+        self.just_closed.clear();
+        self.dropping.clear();
+
+        for (id, window) in self.closed.drain(..) {
+            self.just_closed.push(id);
+            self.dropping.push(window);
+        }
+
+        for desc in self.pending.drain(..) {
+            let window = Window::new(&desc, target);
+            let id = window.id();
+
+            self.windows.insert(id, window);
+            self.inputs.insert(id, Input::new());
+            self.input_evts.insert(id, Vec::new());
+            self.just_opened.push(id);
+        }
+    }
+}
+
+/// The default plugin of `Windows` and `Events<WindowResized>`.
+pub struct WindowPlugin;
+
+impl Plugin for WindowPlugin {
+    fn build(&self, app: &mut AppBuilder) -> Result<(), AppBuildError> {
+        app.insert_resource(Windows::new())
+            .insert_event::<WindowResized>();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_descriptor_json() {
+        let json = r#"{
+            "title": "editor",
+            "width": 800,
+            "min_size": [320, 240],
+            "position": [-10, 20],
+            "mode": "BorderlessFullscreen",
+            "decorations": false
+        }"#;
+
+        let desc = WindowDescriptor::from_json(json).unwrap();
+
+        assert_eq!(desc.title, "editor");
+        assert_eq!((desc.width, desc.height), (800, DEFAULT_HEIGHT));
+        assert_eq!(desc.min_size, Some((320, 240)));
+        assert_eq!(desc.max_size, None);
+        assert_eq!(desc.position, Some((-10, 20)));
+        assert_eq!(desc.mode, WindowMode::BorderlessFullscreen);
+        assert_eq!(desc.present_mode, PresentMode::Mailbox);
+        assert!(desc.resizable && !desc.decorations);
+
+        assert_eq!(
+            WindowDescriptor::from_json(&desc.to_json().unwrap()).unwrap(),
+            desc
+        );
+        assert!(matches!(
+            WindowDescriptor::from_json(r#"{ "width": -1 }"#),
+            Err(JsonError::Parse(_))
+        ));
     }
 }